@@ -1,4 +1,8 @@
 use anchor_lang::prelude::*;
+use pyth_sdk::{
+    Price,
+    DEFAULT_VALID_PERIOD_SECS,
+};
 
 pub mod state;
 use state::AdminConfig;
@@ -36,13 +40,13 @@ pub mod sol_anchor_contract {
         // https://docs.pyth.network/consume-data/best-practices
         let current_timestamp1 = Clock::get()?.unix_timestamp;
         let loan_price = loan_feed
-            .get_price_no_older_than(current_timestamp1, 60)
+            .get_price_no_older_than(current_timestamp1, DEFAULT_VALID_PERIOD_SECS)
             .ok_or(ErrorCode::PythOffline)?;
         let loan_max_price = loan_price
             .price
             .checked_add(loan_price.conf as i64)
             .ok_or(ErrorCode::Overflow)?;
-        let mut loan_max_value = loan_max_price
+        let loan_max_value = loan_max_price
             .checked_mul(loan_qty)
             .ok_or(ErrorCode::Overflow)?;
 
@@ -68,13 +72,13 @@ pub mod sol_anchor_contract {
         // https://docs.pyth.network/consume-data/best-practices
         let current_timestamp2 = Clock::get()?.unix_timestamp;
         let collateral_price = collateral_feed
-            .get_price_no_older_than(current_timestamp2, 60)
+            .get_price_no_older_than(current_timestamp2, DEFAULT_VALID_PERIOD_SECS)
             .ok_or(ErrorCode::PythOffline)?;
         let collateral_min_price = collateral_price
             .price
             .checked_sub(collateral_price.conf as i64)
             .ok_or(ErrorCode::Overflow)?;
-        let mut collateral_min_value = collateral_min_price
+        let collateral_min_value = collateral_min_price
             .checked_mul(collateral_qty)
             .ok_or(ErrorCode::Overflow)?;
 
@@ -96,24 +100,22 @@ pub mod sol_anchor_contract {
 
         // If the loan and collateral prices use different exponent,
         // normalize the value.
-        if loan_price.expo > collateral_price.expo {
-            let normalize = (10 as i64)
-                .checked_pow((loan_price.expo - collateral_price.expo) as u32)
-                .ok_or(ErrorCode::Overflow)?;
-            collateral_min_value = collateral_min_value
-                .checked_mul(normalize)
-                .ok_or(ErrorCode::Overflow)?;
-        } else if loan_price.expo < collateral_price.expo {
-            let normalize = (10 as i64)
-                .checked_pow((collateral_price.expo - loan_price.expo) as u32)
-                .ok_or(ErrorCode::Overflow)?;
-            loan_max_value = loan_max_value
-                .checked_mul(normalize)
-                .ok_or(ErrorCode::Overflow)?;
-        }
+        let mut loan_max = Price {
+            price:        loan_max_value,
+            conf:         0,
+            expo:         loan_price.expo,
+            publish_time: loan_price.publish_time,
+        };
+        let mut collateral_min = Price {
+            price:        collateral_min_value,
+            conf:         0,
+            expo:         collateral_price.expo,
+            publish_time: collateral_price.publish_time,
+        };
+        Price::normalize_pair(&mut loan_max, &mut collateral_min).ok_or(ErrorCode::Overflow)?;
 
         // Check whether the value of the collateral is higher.
-        if collateral_min_value > loan_max_value {
+        if collateral_min.price > loan_max.price {
             msg!("The value of the collateral is higher.");
             return Ok(());
         } else {
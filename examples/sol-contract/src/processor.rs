@@ -19,6 +19,10 @@ use borsh::{
     BorshSerialize,
 };
 use pyth_sdk_solana::state::SolanaPriceAccount;
+use pyth_sdk_solana::{
+    Price,
+    DEFAULT_VALID_PERIOD_SECS,
+};
 
 use crate::instruction::ExampleInstructions;
 use crate::state::AdminConfig;
@@ -88,13 +92,13 @@ pub fn process_instruction(
             let feed1 = SolanaPriceAccount::account_info_to_feed(pyth_loan_account)?;
             let current_timestamp1 = Clock::get()?.unix_timestamp;
             let result1 = feed1
-                .get_price_no_older_than(current_timestamp1, 60)
+                .get_price_no_older_than(current_timestamp1, DEFAULT_VALID_PERIOD_SECS)
                 .ok_or(ProgramError::Custom(3))?;
             let loan_max_price = result1
                 .price
                 .checked_add(result1.conf as i64)
                 .ok_or(ProgramError::Custom(4))?;
-            let mut loan_max_value = loan_max_price
+            let loan_max_value = loan_max_price
                 .checked_mul(loan_qty)
                 .ok_or(ProgramError::Custom(4))?;
             msg!(
@@ -110,13 +114,13 @@ pub fn process_instruction(
             let feed2 = SolanaPriceAccount::account_info_to_feed(pyth_collateral_account)?;
             let current_timestamp2 = Clock::get()?.unix_timestamp;
             let result2 = feed2
-                .get_price_no_older_than(current_timestamp2, 60)
+                .get_price_no_older_than(current_timestamp2, DEFAULT_VALID_PERIOD_SECS)
                 .ok_or(ProgramError::Custom(3))?;
             let collateral_min_price = result2
                 .price
                 .checked_sub(result2.conf as i64)
                 .ok_or(ProgramError::Custom(4))?;
-            let mut collateral_min_value = collateral_min_price
+            let collateral_min_value = collateral_min_price
                 .checked_mul(collateral_qty)
                 .ok_or(ProgramError::Custom(4))?;
             msg!(
@@ -127,24 +131,23 @@ pub fn process_instruction(
 
             // If the loan and collateral prices use different exponent,
             // normalize the value.
-            if result1.expo > result2.expo {
-                let normalize = (10 as i64)
-                    .checked_pow((result1.expo - result2.expo) as u32)
-                    .ok_or(ProgramError::Custom(4))?;
-                collateral_min_value = collateral_min_value
-                    .checked_mul(normalize)
-                    .ok_or(ProgramError::Custom(4))?;
-            } else if result1.expo < result2.expo {
-                let normalize = (10 as i64)
-                    .checked_pow((result2.expo - result1.expo) as u32)
-                    .ok_or(ProgramError::Custom(4))?;
-                loan_max_value = loan_max_value
-                    .checked_mul(normalize)
-                    .ok_or(ProgramError::Custom(4))?;
-            }
+            let mut loan_max = Price {
+                price:        loan_max_value,
+                conf:         0,
+                expo:         result1.expo,
+                publish_time: result1.publish_time,
+            };
+            let mut collateral_min = Price {
+                price:        collateral_min_value,
+                conf:         0,
+                expo:         result2.expo,
+                publish_time: result2.publish_time,
+            };
+            Price::normalize_pair(&mut loan_max, &mut collateral_min)
+                .ok_or(ProgramError::Custom(4))?;
 
             // Check whether the value of the collateral is higher.
-            if collateral_min_value > loan_max_value {
+            if collateral_min.price > loan_max.price {
                 msg!("The value of the collateral is higher.");
                 return Ok(());
             } else {
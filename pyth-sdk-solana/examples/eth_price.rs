@@ -1,6 +1,7 @@
 // example usage of reading pyth price from solana/pythnet price account
 
 use pyth_sdk_solana::state::SolanaPriceAccount;
+use pyth_sdk_solana::DEFAULT_VALID_PERIOD_SECS;
 use solana_client::rpc_client::RpcClient;
 use solana_program::pubkey::Pubkey;
 use std::str::FromStr;
@@ -34,7 +35,8 @@ fn main() {
             .unwrap()
             .as_secs() as i64;
 
-        let maybe_price = eth_price_feed.get_price_no_older_than(current_time, 60);
+        let maybe_price =
+            eth_price_feed.get_price_no_older_than(current_time, DEFAULT_VALID_PERIOD_SECS);
         match maybe_price {
             Some(p) => {
                 println!("price ........... {} x 10^{}", p.price, p.expo);
@@ -47,7 +49,8 @@ fn main() {
         }
 
 
-        let maybe_ema_price = eth_price_feed.get_ema_price_no_older_than(current_time, 60);
+        let maybe_ema_price =
+            eth_price_feed.get_ema_price_no_older_than(current_time, DEFAULT_VALID_PERIOD_SECS);
         match maybe_ema_price {
             Some(ema_price) => {
                 println!(
@@ -0,0 +1,85 @@
+//! Helpers for reading Solana-format price data without the Solana runtime, e.g. from programs on
+//! other chains.
+
+use pyth_sdk::{
+    DurationInSeconds,
+    Price,
+    PriceIdentifier,
+    UnixTimestamp,
+};
+
+use crate::state::load_price_account;
+use crate::PythError;
+
+/// Get the price from the raw byte value of a Solana-format price account, as long as it was
+/// published within `max_age` seconds of `current_time`.
+///
+/// This bundles `load_price_account` with the freshness check of
+/// `PriceFeed::get_price_no_older_than`. It does not rely on the Solana runtime (e.g. the `Clock`
+/// sysvar), so it is available without the `solana-program` feature.
+pub fn price_from_solana_bytes_checked(
+    data: &[u8],
+    current_time: UnixTimestamp,
+    max_age: DurationInSeconds,
+) -> Result<Price, PythError> {
+    load_price_account::<32, ()>(data)?
+        .price_feed_with_id(PriceIdentifier::default())
+        .get_price_no_older_than(current_time, max_age)
+        .ok_or(PythError::StalePrice)
+}
+
+#[cfg(test)]
+mod test {
+    use pyth_sdk::Price;
+
+    use super::price_from_solana_bytes_checked;
+    use crate::state::{
+        AccountType,
+        PriceInfo,
+        PriceStatus,
+        SolanaPriceAccount,
+        MAGIC,
+        VERSION_2,
+    };
+    use crate::PythError;
+
+    #[test]
+    fn test_price_from_solana_bytes_checked() {
+        let price_account = SolanaPriceAccount {
+            magic: MAGIC,
+            ver: VERSION_2,
+            atype: AccountType::Price as u32,
+            expo: 5,
+            agg: PriceInfo {
+                price: 10,
+                conf: 20,
+                status: PriceStatus::Trading,
+                ..Default::default()
+            },
+            timestamp: 200,
+            ..Default::default()
+        };
+        let data = bytemuck::bytes_of(&price_account);
+
+        assert_eq!(
+            price_from_solana_bytes_checked(data, 210, 10),
+            Ok(Price {
+                conf:         20,
+                expo:         5,
+                price:        10,
+                publish_time: 200,
+            })
+        );
+        assert_eq!(
+            price_from_solana_bytes_checked(data, 211, 10),
+            Err(PythError::StalePrice)
+        );
+        assert_eq!(
+            price_from_solana_bytes_checked(&data[..10], 210, 10),
+            Err(PythError::AccountTooSmall {
+                expected: std::mem::size_of::<SolanaPriceAccount>(),
+                actual:   10,
+            })
+        );
+    }
+}
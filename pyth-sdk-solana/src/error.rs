@@ -1,12 +1,16 @@
-use num_derive::FromPrimitive;
+use num_traits::FromPrimitive;
+#[cfg(feature = "solana-program")]
 use solana_program::program_error::ProgramError;
+use std::convert::TryFrom;
 use thiserror::Error;
 
 /// Errors that may be returned by Pyth.
-#[derive(Clone, Debug, Eq, Error, FromPrimitive, PartialEq)]
+///
+/// See [`PythError::code`] for the custom error code of each variant.
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
 pub enum PythError {
     // 0
-    /// Invalid account data -- either insufficient data, or incorrect magic number
+    /// Invalid account data that cannot be read as a Pyth account
     #[error("Failed to convert account into a Pyth account")]
     InvalidAccountData,
     /// Wrong version number
@@ -16,10 +20,100 @@ pub enum PythError {
     /// a price account as a product account.
     #[error("Incorrect account type")]
     WrongAccountType,
+    /// The price was not published recently enough to be used.
+    #[error("Price is too old")]
+    StalePrice,
+    /// The account is not owned by the expected program, e.g., the Pyth oracle program.
+    #[error("Account is not owned by the expected program")]
+    InvalidOwner,
+    /// The account data is smaller than the account type being read.
+    #[error("Account data too small: expected at least {expected} bytes, got {actual}")]
+    AccountTooSmall { expected: usize, actual: usize },
+    /// The account data does not start with the Pyth magic number.
+    #[error("Incorrect magic number for Pyth account: {found:#x}")]
+    WrongMagic { found: u32 },
 }
 
+impl PythError {
+    /// The custom error code of this error, used for the corresponding `ProgramError`. These codes
+    /// are part of the on-chain interface and must not change.
+    pub fn code(&self) -> u32 {
+        match self {
+            PythError::InvalidAccountData => 0,
+            PythError::BadVersionNumber => 1,
+            PythError::WrongAccountType => 2,
+            PythError::StalePrice => 3,
+            PythError::InvalidOwner => 4,
+            PythError::AccountTooSmall { .. } => 5,
+            PythError::WrongMagic { .. } => 6,
+        }
+    }
+}
+
+/// Converts an error code back into a `PythError`. Error codes do not carry the details of
+/// `AccountTooSmall` and `WrongMagic`, so their fields are set to 0.
+impl FromPrimitive for PythError {
+    fn from_i64(n: i64) -> Option<Self> {
+        u64::try_from(n).ok().and_then(Self::from_u64)
+    }
+
+    fn from_u64(n: u64) -> Option<Self> {
+        match n {
+            0 => Some(PythError::InvalidAccountData),
+            1 => Some(PythError::BadVersionNumber),
+            2 => Some(PythError::WrongAccountType),
+            3 => Some(PythError::StalePrice),
+            4 => Some(PythError::InvalidOwner),
+            5 => Some(PythError::AccountTooSmall {
+                expected: 0,
+                actual:   0,
+            }),
+            6 => Some(PythError::WrongMagic { found: 0 }),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "solana-program")]
 impl From<PythError> for ProgramError {
     fn from(e: PythError) -> Self {
-        ProgramError::Custom(e as u32)
+        ProgramError::Custom(e.code())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use num_traits::FromPrimitive;
+    #[cfg(feature = "solana-program")]
+    use solana_program::program_error::ProgramError;
+
+    use super::PythError;
+
+    #[test]
+    fn test_error_codes() {
+        let errors = [
+            PythError::InvalidAccountData,
+            PythError::BadVersionNumber,
+            PythError::WrongAccountType,
+            PythError::StalePrice,
+            PythError::InvalidOwner,
+            PythError::AccountTooSmall {
+                expected: 0,
+                actual:   0,
+            },
+            PythError::WrongMagic { found: 0 },
+        ];
+
+        for (code, error) in errors.iter().enumerate() {
+            assert_eq!(error.code(), code as u32);
+            #[cfg(feature = "solana-program")]
+            assert_eq!(
+                ProgramError::from(error.clone()),
+                ProgramError::Custom(code as u32)
+            );
+            assert_eq!(PythError::from_u32(code as u32).as_ref(), Some(error));
+        }
+        assert_eq!(PythError::from_u32(errors.len() as u32), None);
+        assert_eq!(PythError::from_i64(-1), None);
     }
 }
@@ -2,18 +2,30 @@
 //!
 //! Please see the [crates.io page](https://crates.io/crates/pyth-sdk-solana/) for documentation and example usage.
 
+pub use self::cross_chain::price_from_solana_bytes_checked;
 pub use self::error::PythError;
+#[cfg(feature = "async")]
+pub use self::loader::AsyncPriceLoader;
 
+mod cross_chain;
 mod error;
+#[cfg(feature = "async")]
+mod loader;
 pub mod state;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
+mod utils;
 
+#[cfg(feature = "solana-program")]
 use solana_program::account_info::{
     Account,
     AccountInfo,
     IntoAccountInfo,
 };
+#[cfg(feature = "solana-program")]
 use solana_program::pubkey::Pubkey;
 
+#[cfg(feature = "solana-program")]
 use state::{
     load_price_account,
     GenericPriceAccount,
@@ -25,12 +37,20 @@ pub use pyth_sdk::{
     PriceFeed,
     PriceIdentifier,
     ProductIdentifier,
+    DEFAULT_VALID_PERIOD_SECS,
 };
 
 /// Maximum valid slot period before price is considered to be stale.
 pub const VALID_SLOT_PERIOD: u64 = 25;
 
+/// Get the identifier of the price feed stored in the price account with key `key`.
+#[cfg(feature = "solana-program")]
+pub fn identifier_from_pubkey(key: &Pubkey) -> PriceIdentifier {
+    PriceIdentifier::new(key.to_bytes())
+}
+
 /// Loads Pyth Feed Price from Price Account Info.
+#[cfg(feature = "solana-program")]
 #[deprecated(note = "solana-specific, use SolanaPriceAccount::account_info_to_feed instead.")]
 pub fn load_price_feed_from_account_info(
     price_account_info: &AccountInfo,
@@ -41,6 +61,7 @@ pub fn load_price_feed_from_account_info(
 /// Loads Pyth Price Feed from Account when using Solana Client.
 ///
 /// It is a helper function which constructs Account Info when reading Account in clients.
+#[cfg(feature = "solana-program")]
 #[deprecated(note = "solana-specific, use SolanaPriceAccount::account_to_feed instead.")]
 pub fn load_price_feed_from_account(
     price_key: &Pubkey,
@@ -49,6 +70,7 @@ pub fn load_price_feed_from_account(
     SolanaPriceAccount::account_to_feed(price_key, price_account)
 }
 
+#[cfg(feature = "solana-program")]
 impl<const N: usize, T: 'static> GenericPriceAccount<N, T>
 where
     T: Default,
@@ -63,6 +85,35 @@ where
         .map(|acc| acc.to_price_feed(price_account_info.key))
     }
 
+    /// Get an owned copy of the price account stored in `price_account_info`, e.g., to read the
+    /// publisher components or other fields that `account_info_to_feed` discards.
+    pub fn account_info_to_price_account(
+        price_account_info: &AccountInfo,
+    ) -> Result<GenericPriceAccount<N, T>, PythError> {
+        load_price_account::<N, T>(
+            *price_account_info
+                .try_borrow_data()
+                .map_err(|_| PythError::InvalidAccountData)?,
+        )
+        .copied()
+    }
+
+    /// Same as `account_info_to_feed`, but first checks that the account is owned by
+    /// `expected_owner`, which should be the Pyth oracle program of the cluster.
+    ///
+    /// Without this check, any account holding data in the price account format can be
+    /// substituted for a Pyth price account.
+    pub fn account_info_to_feed_with_owner(
+        price_account_info: &AccountInfo,
+        expected_owner: &Pubkey,
+    ) -> Result<PriceFeed, PythError> {
+        if price_account_info.owner != expected_owner {
+            return Err(PythError::InvalidOwner);
+        }
+
+        Self::account_info_to_feed(price_account_info)
+    }
+
     pub fn account_to_feed(
         price_key: &Pubkey,
         price_account: &mut impl Account,
@@ -71,3 +122,116 @@ where
         Self::account_info_to_feed(&price_account_info)
     }
 }
+
+#[cfg(all(test, feature = "solana-program"))]
+mod test {
+    use solana_program::account_info::AccountInfo;
+    use solana_program::pubkey::Pubkey;
+
+    use crate::state::{
+        AccountType,
+        PriceInfo,
+        PriceStatus,
+        SolanaPriceAccount,
+        MAGIC,
+        VERSION_2,
+    };
+    use crate::{
+        identifier_from_pubkey,
+        PythError,
+    };
+
+    #[test]
+    fn test_identifier_from_pubkey() {
+        let key = Pubkey::new_from_array([
+            1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24,
+            25, 26, 27, 28, 29, 30, 31, 32,
+        ]);
+        assert_eq!(identifier_from_pubkey(&key).to_bytes(), key.to_bytes());
+    }
+
+    #[test]
+    fn test_account_info_to_price_account() {
+        let mut price_account = SolanaPriceAccount {
+            magic: MAGIC,
+            ver: VERSION_2,
+            atype: AccountType::Price as u32,
+            num_qt: 3,
+            min_pub: 2,
+            agg: PriceInfo {
+                price: 12345,
+                conf: 67,
+                status: PriceStatus::Trading,
+                pub_slot: 1000,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let expected = price_account;
+        let key = Pubkey::new_from_array([1; 32]);
+        let owner = Pubkey::new_from_array([2; 32]);
+        let mut lamports = 0;
+        let account_info = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            bytemuck::bytes_of_mut(&mut price_account),
+            &owner,
+            false,
+            0,
+        );
+
+        let parsed = SolanaPriceAccount::account_info_to_price_account(&account_info).unwrap();
+        assert_eq!(parsed, expected);
+        assert_eq!(parsed.num_qt, 3);
+        assert_eq!(parsed.min_pub, 2);
+        assert_eq!(parsed.agg, expected.agg);
+        assert_eq!(
+            parsed.to_price_feed(&key),
+            SolanaPriceAccount::account_info_to_feed(&account_info).unwrap()
+        );
+
+        account_info.try_borrow_mut_data().unwrap()[0] = 0;
+        assert!(matches!(
+            SolanaPriceAccount::account_info_to_price_account(&account_info),
+            Err(PythError::WrongMagic { .. })
+        ));
+    }
+
+    #[test]
+    fn test_account_info_to_feed_with_owner() {
+        let mut price_account = SolanaPriceAccount {
+            magic: MAGIC,
+            ver: VERSION_2,
+            atype: AccountType::Price as u32,
+            ..Default::default()
+        };
+        let key = Pubkey::new_from_array([1; 32]);
+        let owner = Pubkey::new_from_array([2; 32]);
+        let mut lamports = 0;
+        let account_info = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            bytemuck::bytes_of_mut(&mut price_account),
+            &owner,
+            false,
+            0,
+        );
+
+        assert_eq!(
+            SolanaPriceAccount::account_info_to_feed_with_owner(&account_info, &owner),
+            SolanaPriceAccount::account_info_to_feed(&account_info)
+        );
+        assert!(SolanaPriceAccount::account_info_to_feed_with_owner(&account_info, &owner).is_ok());
+        assert_eq!(
+            SolanaPriceAccount::account_info_to_feed_with_owner(
+                &account_info,
+                &Pubkey::new_from_array([3; 32])
+            ),
+            Err(PythError::InvalidOwner)
+        );
+    }
+}
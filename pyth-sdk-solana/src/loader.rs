@@ -0,0 +1,157 @@
+//! Asynchronous loading of price feeds, e.g., for services using a non-blocking RPC client.
+
+use std::future::Future;
+
+use solana_program::pubkey::Pubkey;
+
+use crate::state::{
+    load_price_account,
+    SolanaPriceAccount,
+};
+use crate::{
+    PriceFeed,
+    PythError,
+};
+
+/// Loads Pyth price feeds from their price accounts asynchronously.
+///
+/// This trait is implemented for any function that takes the key of a price account and returns
+/// a future resolving to the raw account data, so that an async RPC client can be plugged in with
+/// a closure:
+///
+/// ```ignore
+/// let loader = |key: Pubkey| {
+///     let client = client.clone();
+///     async move {
+///         client
+///             .get_account_data(&key)
+///             .await
+///             .map_err(|_| PythError::InvalidAccountData)
+///     }
+/// };
+/// let price_feed = loader.load_feed(&price_key).await?;
+/// ```
+pub trait AsyncPriceLoader {
+    /// Load the price feed stored in the price account `key`.
+    fn load_feed(&self, key: &Pubkey) -> impl Future<Output = Result<PriceFeed, PythError>> + Send;
+}
+
+impl<F, Fut> AsyncPriceLoader for F
+where
+    F: Fn(Pubkey) -> Fut,
+    Fut: Future<Output = Result<Vec<u8>, PythError>> + Send,
+{
+    fn load_feed(&self, key: &Pubkey) -> impl Future<Output = Result<PriceFeed, PythError>> + Send {
+        let key = *key;
+        let data = self(key);
+
+        async move {
+            let data = data.await?;
+            let price_account: &SolanaPriceAccount = load_price_account(&data)?;
+            Ok(price_account.to_price_feed(&key))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::future::Future;
+    use std::sync::Arc;
+    use std::task::{
+        Context,
+        Poll,
+        Wake,
+        Waker,
+    };
+
+    use solana_program::pubkey::Pubkey;
+
+    use super::AsyncPriceLoader;
+    use crate::state::{
+        AccountType,
+        PriceInfo,
+        PriceStatus,
+        SolanaPriceAccount,
+        MAGIC,
+        VERSION_2,
+    };
+    use crate::{
+        Price,
+        PythError,
+    };
+
+    /// Minimal executor, polling `future` until it completes.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        struct NoopWaker;
+
+        impl Wake for NoopWaker {
+            fn wake(self: Arc<Self>) {
+            }
+        }
+
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut context = Context::from_waker(&waker);
+        let mut future = Box::pin(future);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+        }
+    }
+
+    #[test]
+    fn test_load_feed() {
+        let price_key = Pubkey::new_from_array([1; 32]);
+        let price_account = SolanaPriceAccount {
+            magic: MAGIC,
+            ver: VERSION_2,
+            atype: AccountType::Price as u32,
+            expo: 5,
+            agg: PriceInfo {
+                price: 10,
+                conf: 20,
+                status: PriceStatus::Trading,
+                ..Default::default()
+            },
+            timestamp: 200,
+            ..Default::default()
+        };
+        let data = bytemuck::bytes_of(&price_account).to_vec();
+
+        let fetcher = |key: Pubkey| {
+            let result = if key == price_key {
+                Ok(data.clone())
+            } else {
+                Err(PythError::InvalidAccountData)
+            };
+            async move { result }
+        };
+
+        let price_feed = block_on(fetcher.load_feed(&price_key)).unwrap();
+        assert_eq!(price_feed.id.to_bytes(), price_key.to_bytes());
+        assert_eq!(
+            price_feed.get_price_unchecked(),
+            Price {
+                conf:         20,
+                expo:         5,
+                price:        10,
+                publish_time: 200,
+            }
+        );
+
+        assert_eq!(
+            block_on(fetcher.load_feed(&Pubkey::new_from_array([2; 32]))),
+            Err(PythError::InvalidAccountData)
+        );
+
+        // The fetched data is not a price account.
+        let fetcher = |_: Pubkey| async { Ok(vec![0u8; 10]) };
+        assert_eq!(
+            block_on(fetcher.load_feed(&price_key)),
+            Err(PythError::AccountTooSmall {
+                expected: std::mem::size_of::<SolanaPriceAccount>(),
+                actual:   10,
+            })
+        );
+    }
+}
@@ -5,19 +5,20 @@ use borsh::{
     BorshSerialize,
 };
 use bytemuck::{
+    bytes_of,
     cast_slice,
     from_bytes,
     try_cast_slice,
     Pod,
-    PodCastError,
     Zeroable,
 };
 use pyth_sdk::{
     PriceIdentifier,
     UnixTimestamp,
 };
+#[cfg(feature = "solana-program")]
 use solana_program::clock::Clock;
-use solana_program::pubkey::Pubkey;
+use std::collections::HashMap;
 use std::mem::size_of;
 
 pub use pyth_sdk::{
@@ -27,6 +28,23 @@ pub use pyth_sdk::{
 
 use crate::PythError;
 
+#[cfg(feature = "solana-program")]
+pub use solana_program::pubkey::Pubkey;
+
+/// Raw public key, used in place of `solana_program::pubkey::Pubkey` when the `solana-program`
+/// feature is disabled. Both have the same memory layout, so accounts are parsed identically.
+#[cfg(not(feature = "solana-program"))]
+pub type Pubkey = [u8; 32];
+
+// Accounts are parsed by reinterpreting their little-endian on-chain bytes in place, so the `Pod`
+// implementations below only exist on little-endian targets. Fail with a clear message instead of
+// confusing missing trait/function errors elsewhere.
+#[cfg(target_endian = "big")]
+compile_error!(
+    "pyth-sdk-solana only supports little-endian targets: Pyth accounts are parsed by \
+     reinterpreting their little-endian on-chain bytes in place."
+);
+
 pub const MAGIC: u32 = 0xa1b2c3d4;
 pub const VERSION_2: u32 = 2;
 pub const VERSION: u32 = VERSION_2;
@@ -162,6 +180,15 @@ pub struct MappingAccount {
     pub products: [Pubkey; MAP_TABLE_SIZE],
 }
 
+impl MappingAccount {
+    /// Iterate over the product keys listed in this mapping account, i.e., the first `num` entries
+    /// of `products` (at most `MAP_TABLE_SIZE`). Products listed in the `next` mapping account are
+    /// not included.
+    pub fn iter_products(&self) -> impl Iterator<Item = &Pubkey> {
+        self.products.iter().take(self.num as usize)
+    }
+}
+
 #[cfg(target_endian = "little")]
 unsafe impl Zeroable for MappingAccount {
 }
@@ -191,9 +218,36 @@ pub struct ProductAccount {
 
 impl ProductAccount {
     pub fn iter(&self) -> AttributeIter {
+        let attrs_len = (self.size as usize)
+            .saturating_sub(PROD_HDR_SIZE)
+            .min(PROD_ATTR_SIZE);
         AttributeIter {
-            attrs: &self.attr[..(self.size as usize - PROD_HDR_SIZE)],
+            attrs: &self.attr[..attrs_len],
+        }
+    }
+
+    /// Get the value of the first attribute named `key`, e.g., `"asset_type"`.
+    pub fn get_attribute(&self, key: &str) -> Option<&str> {
+        self.iter()
+            .find(|(attr_key, _)| *attr_key == key)
+            .map(|(_, value)| value)
+    }
+
+    /// Collect the attributes of the product into a map from key to value, e.g., to look up
+    /// several attributes without scanning the attributes once per key.
+    ///
+    /// If a key occurs more than once, the map holds its first value, matching `get_attribute`.
+    pub fn attributes(&self) -> HashMap<&str, &str> {
+        let mut attributes = HashMap::new();
+        for (key, value) in self.iter() {
+            attributes.entry(key).or_insert(value);
         }
+        attributes
+    }
+
+    /// Get the `"symbol"` attribute of the product, e.g., `"Crypto.BTC/USD"`.
+    pub fn symbol(&self) -> Option<&str> {
+        self.get_attribute("symbol")
     }
 }
 
@@ -260,6 +314,34 @@ pub struct PriceComp {
     pub latest:    PriceInfo,
 }
 
+impl PriceComp {
+    /// Get the price used to compute the current aggregate price as a `Price` with exponent
+    /// `expo`, which should be the exponent of the price account.
+    ///
+    /// Publisher components do not carry a timestamp, so the publish time is set to 0.
+    pub fn agg_price(&self, expo: i32) -> Price {
+        Price {
+            conf: self.agg.conf,
+            expo,
+            price: self.agg.price,
+            publish_time: 0,
+        }
+    }
+
+    /// Get the publisher's latest price as a `Price` with exponent `expo`, which should be the
+    /// exponent of the price account.
+    ///
+    /// Publisher components do not carry a timestamp, so the publish time is set to 0.
+    pub fn latest_price(&self, expo: i32) -> Price {
+        Price {
+            conf: self.latest.conf,
+            expo,
+            price: self.latest.price,
+            publish_time: 0,
+        }
+    }
+}
+
 #[deprecated = "Type is renamed to Rational, please use the new name."]
 pub type Ema = Rational;
 
@@ -284,7 +366,7 @@ pub struct Rational {
 }
 
 #[repr(C)]
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct GenericPriceAccount<const N: usize, T>
 where
     T: Default,
@@ -339,6 +421,7 @@ where
     /// aggregate price info
     pub agg:            PriceInfo,
     /// price components one per quoter
+    #[serde(with = "crate::utils::as_array")]
     pub comp:           [PriceComp; N],
     /// additional extended account data
     pub extended:       T,
@@ -393,7 +476,9 @@ where
 }
 
 #[repr(C)]
-#[derive(Copy, Clone, Debug, Default, Pod, Zeroable, PartialEq, Eq)]
+#[derive(
+    Copy, Clone, Debug, Default, Pod, Zeroable, PartialEq, Eq, serde::Serialize, serde::Deserialize,
+)]
 pub struct PriceCumulative {
     /// Cumulative sum of price * slot_gap
     pub price:          i128,
@@ -402,13 +487,38 @@ pub struct PriceCumulative {
     /// Cumulative number of slots where the price wasn't recently updated (within
     /// PC_MAX_SEND_LATENCY slots). This field should be used to calculate the downtime
     /// as a percent of slots between two times `T` and `t` as follows:
-    /// `(T.num_down_slots - t.num_down_slots) / (T.agg_.pub_slot_ - t.agg_.pub_slot_)`
+    /// `(T.num_down_slots - t.num_down_slots) / (T.agg_.pub_slot_ - t.agg_.pub_slot_)`,
+    /// which is implemented by `PriceCumulative::downtime_ratio`.
     pub num_down_slots: u64,
     /// Padding for alignment
     pub unused:         u64,
 }
 
-#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+impl PriceCumulative {
+    /// Get the fraction of slots between the snapshots `start` and `end` of the cumulative values
+    /// in which the price wasn't recently updated, where `start_slot` and `end_slot` are the
+    /// `agg.pub_slot` of the price account at the time of each snapshot.
+    ///
+    /// Returns `None` if `end_slot` is not after `start_slot`, or if `end` has fewer down slots
+    /// than `start`, i.e., the snapshots are swapped or of different accounts.
+    pub fn downtime_ratio(
+        start: &PriceCumulative,
+        start_slot: u64,
+        end: &PriceCumulative,
+        end_slot: u64,
+    ) -> Option<f64> {
+        let slot_diff = end_slot.checked_sub(start_slot)?;
+        if slot_diff == 0 {
+            return None;
+        }
+
+        let down_slots = end.num_down_slots.checked_sub(start.num_down_slots)?;
+
+        Some(down_slots as f64 / slot_diff as f64)
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct PriceAccountExt {
     pub price_cumulative: PriceCumulative,
 }
@@ -431,6 +541,144 @@ unsafe impl<const N: usize, T: Default + Copy> Zeroable for GenericPriceAccount<
 unsafe impl<const N: usize, T: Default + Copy + 'static> Pod for GenericPriceAccount<N, T> {
 }
 
+impl<const N: usize, T> GenericPriceAccount<N, T>
+where
+    T: Default,
+    T: Copy,
+    T: 'static,
+{
+    /// Get the raw bytes of this account, i.e., its on-chain representation.
+    ///
+    /// Together with `from_bytes`, this allows building price account data for tests from a
+    /// struct literal rather than by hand.
+    pub fn as_bytes(&self) -> &[u8] {
+        bytes_of(self)
+    }
+
+    /// Get a price account from its raw bytes. This is the same as `load_price_account`: `data`
+    /// must be at least as large as the account and have the expected magic number, version and
+    /// account type.
+    pub fn from_bytes(data: &[u8]) -> Result<&Self, PythError> {
+        load_price_account(data)
+    }
+}
+
+/// Which of the prices stored in a price account was used to build a `PriceFeed`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PriceSource {
+    /// The current aggregate price, used when the aggregate status is Trading.
+    Aggregate,
+    /// The previous aggregate price with Trading status, used as a fallback otherwise.
+    Previous,
+}
+
+/// A `PriceFeed` along with the slot in which its current price was published, as returned by
+/// `GenericPriceAccount::to_price_feed_with_slot`.
+///
+/// This allows checking the freshness of the price against the current slot, which is cheaper to
+/// obtain on-chain than a trusted unix timestamp.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PriceFeedWithSlot {
+    pub price_feed: PriceFeed,
+    /// The slot in which the current price of `price_feed` was published.
+    pub pub_slot:   u64,
+}
+
+impl PriceFeedWithSlot {
+    pub fn new(price_feed: PriceFeed, pub_slot: u64) -> PriceFeedWithSlot {
+        PriceFeedWithSlot {
+            price_feed,
+            pub_slot,
+        }
+    }
+
+    /// Get the current price as long as it was published within `slot_threshold` slots of
+    /// `current_slot`. See `GenericPriceAccount::get_price_no_older_than`.
+    pub fn get_price_no_older_than_slots(
+        &self,
+        current_slot: u64,
+        slot_threshold: u64,
+    ) -> Option<Price> {
+        if current_slot.saturating_sub(self.pub_slot) <= slot_threshold {
+            Some(self.price_feed.get_price_unchecked())
+        } else {
+            None
+        }
+    }
+}
+
+/// Determines how the freshness of a price is checked by `get_price_with_policy`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FreshnessPolicy {
+    /// The price must have been published within this many slots of the current slot.
+    Slots(u64),
+    /// The price must have been published within this many seconds of the current time.
+    Seconds(u64),
+    /// The price must satisfy both the slot and the time constraints.
+    Both { slots: u64, secs: u64 },
+}
+
+/// A price account in either the Solana or the Pythnet layout, see `load_price_account_any`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ParsedPriceAccount<'a> {
+    Solana(&'a SolanaPriceAccount),
+    Pythnet(&'a PythnetPriceAccount),
+}
+
+impl ParsedPriceAccount<'_> {
+    /// See `GenericPriceAccount::to_price_feed`.
+    #[cfg(feature = "solana-program")]
+    pub fn to_price_feed(&self, price_key: &Pubkey) -> PriceFeed {
+        match self {
+            ParsedPriceAccount::Solana(price_account) => price_account.to_price_feed(price_key),
+            ParsedPriceAccount::Pythnet(price_account) => price_account.to_price_feed(price_key),
+        }
+    }
+}
+
+/// The header fields of a price account, as returned by `GenericPriceAccount::header`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PriceAccountHeader {
+    /// pyth magic number
+    pub magic: u32,
+    /// program version
+    pub ver:   u32,
+    /// account type
+    pub atype: u32,
+    /// price account size
+    pub size:  u32,
+    /// price or calculation type
+    pub ptype: PriceType,
+    /// price exponent
+    pub expo:  i32,
+}
+
+/// Details about raw price account data, as reported by `diagnose_price_account`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PriceAccountDiagnosis {
+    /// Whether the data is large enough to hold at least a `SolanaPriceAccount`.
+    pub length_ok:       bool,
+    /// The magic number, which should be `MAGIC`. `None` if the data is too short.
+    pub magic:           Option<u32>,
+    /// The version number, which should be `VERSION_2`. `None` if the data is too short.
+    pub version:         Option<u32>,
+    /// The account type, which should be `AccountType::Price`. `None` if the data is too short.
+    pub account_type:    Option<u32>,
+    /// The number of publisher components of the layout inferred from the length of the data,
+    /// i.e., 128 for Pythnet and 32 for Solana. `None` if the data is too short for either layout.
+    pub component_count: Option<usize>,
+}
+
+impl PriceAccountDiagnosis {
+    /// Whether `load_price_account` accepts data with this diagnosis for the inferred layout.
+    pub fn is_valid(&self) -> bool {
+        self.length_ok
+            && self.magic == Some(MAGIC)
+            && self.version == Some(VERSION_2)
+            && self.account_type == Some(AccountType::Price as u32)
+    }
+}
+
 impl<const N: usize, T> GenericPriceAccount<N, T>
 where
     T: Default,
@@ -443,12 +691,137 @@ where
         }
     }
 
+    /// Get a copy of the header fields of this account, e.g., for routing accounts without reading
+    /// the individual fields.
+    ///
+    /// Returns an error if the magic number, version or account type are not those of a price
+    /// account, in the same way as `load_price_account`.
+    pub fn header(&self) -> Result<PriceAccountHeader, PythError> {
+        if self.magic != MAGIC {
+            return Err(PythError::WrongMagic { found: self.magic });
+        }
+        if self.ver != VERSION_2 {
+            return Err(PythError::BadVersionNumber);
+        }
+        if self.atype != AccountType::Price as u32 {
+            return Err(PythError::WrongAccountType);
+        }
+
+        Ok(PriceAccountHeader {
+            magic: self.magic,
+            ver:   self.ver,
+            atype: self.atype,
+            size:  self.size,
+            ptype: self.ptype,
+            expo:  self.expo,
+        })
+    }
+
+    /// Check whether this account is a price account holding a price, i.e., its account type is
+    /// `AccountType::Price` and its price type is `PriceType::Price`.
+    pub fn is_price_type(&self) -> bool {
+        self.atype == AccountType::Price as u32 && self.ptype == PriceType::Price
+    }
+
+    /// Get a copy of the aggregate price info, including its status, corporate action and
+    /// publish slot.
+    pub fn aggregate_info(&self) -> PriceInfo {
+        self.agg
+    }
+
+    /// Iterate over the populated publisher components, i.e., the first `num` entries of `comp`
+    /// (at most `N`), skipping the empty slots at the end.
+    pub fn iter_price_comps(&self) -> impl Iterator<Item = &PriceComp> {
+        self.comp.iter().take(self.num as usize)
+    }
+
+    /// Get the number of populated publisher components, i.e., `num` clamped to `N`.
+    pub fn num_publishers(&self) -> usize {
+        self.iter_price_comps().count()
+    }
+
+    /// Check whether this account has no publishers, i.e., `num` is 0 or every populated publisher
+    /// component has the default (all-zero) publisher key.
+    ///
+    /// Such accounts, e.g., uninitialized or abandoned price accounts, never carry a meaningful
+    /// price, so consumers can reject them early rather than reading stale or default data.
+    pub fn is_degenerate(&self) -> bool {
+        self.iter_price_comps()
+            .all(|comp| comp.publisher == Pubkey::default())
+    }
+
+    /// Get the number of populated publisher components whose `agg` price has TRADING status.
+    pub fn num_trading(&self) -> usize {
+        self.iter_price_comps()
+            .filter(|comp| comp.agg.status == PriceStatus::Trading)
+            .count()
+    }
+
+    /// Recompute an aggregate price from the publisher components, as the median of the `agg`
+    /// prices of the components with TRADING status.
+    ///
+    /// The confidence is the median of the confidences of the same components, and the publish
+    /// time is the timestamp of the aggregate price. With an even number of trading components,
+    /// the mean of the two middle values is used (rounded towards zero). Returns `None` if fewer
+    /// than `min_pub` components (or none at all) are trading.
+    pub fn compute_component_median(&self) -> Option<Price> {
+        let trading: Vec<&PriceInfo> = self
+            .iter_price_comps()
+            .map(|comp| &comp.agg)
+            .filter(|agg| agg.status == PriceStatus::Trading)
+            .collect();
+
+        if trading.len() < self.min_pub as usize {
+            return None;
+        }
+
+        let price = median(trading.iter().map(|agg| agg.price as i128).collect())?;
+        let conf = median(trading.iter().map(|agg| agg.conf as i128).collect())?;
+
+        Some(Price {
+            conf:         conf as u64,
+            expo:         self.expo,
+            price:        price as i64,
+            publish_time: self.timestamp,
+        })
+    }
+
     /// Get the last valid price as long as it was updated within `slot_threshold` slots of the
     /// current slot.
+    #[cfg(feature = "solana-program")]
     pub fn get_price_no_older_than(&self, clock: &Clock, slot_threshold: u64) -> Option<Price> {
-        if self.agg.status == PriceStatus::Trading
-            && self.agg.pub_slot >= clock.slot - slot_threshold
-        {
+        self.get_price_no_older_than_slot(clock.slot, slot_threshold)
+    }
+
+    /// Same as `get_price_no_older_than`, but also returns `None` unless the aggregate price was
+    /// computed from at least `min_publishers` quoters (`num_qt`).
+    ///
+    /// A fresh price contributed by only a few publishers can be easy to manipulate, so this
+    /// combines the freshness check with a minimum number of quoters.
+    #[cfg(feature = "solana-program")]
+    pub fn get_price_no_older_than_min_pub(
+        &self,
+        clock: &Clock,
+        slot_threshold: u64,
+        min_publishers: u32,
+    ) -> Option<Price> {
+        if self.num_qt < min_publishers {
+            return None;
+        }
+
+        self.get_price_no_older_than(clock, slot_threshold)
+    }
+
+    /// Same as `get_price_no_older_than`, but takes the current slot directly rather than a
+    /// `Clock`, e.g., for off-chain indexers that track the slot themselves.
+    pub fn get_price_no_older_than_slot(
+        &self,
+        current_slot: u64,
+        slot_threshold: u64,
+    ) -> Option<Price> {
+        let min_slot = current_slot.saturating_sub(slot_threshold);
+
+        if self.agg.status == PriceStatus::Trading && self.agg.pub_slot >= min_slot {
             return Some(Price {
                 conf:         self.agg.conf,
                 expo:         self.expo,
@@ -457,7 +830,7 @@ where
             });
         }
 
-        if self.prev_slot >= clock.slot - slot_threshold {
+        if self.prev_slot >= min_slot {
             return Some(Price {
                 conf:         self.prev_conf,
                 expo:         self.expo,
@@ -469,10 +842,148 @@ where
         None
     }
 
+    /// Get the last valid price as long as it is fresh according to `policy`.
+    ///
+    /// Slot-based freshness follows `get_price_no_older_than`, while time-based freshness compares
+    /// the publish time of the price to `clock.unix_timestamp`.
+    #[cfg(feature = "solana-program")]
+    pub fn get_price_with_policy(&self, clock: &Clock, policy: FreshnessPolicy) -> Option<Price> {
+        let (price, max_age) = match policy {
+            FreshnessPolicy::Slots(slots) => return self.get_price_no_older_than(clock, slots),
+            FreshnessPolicy::Seconds(secs) => (self.get_current_price(), secs),
+            FreshnessPolicy::Both { slots, secs } => {
+                (self.get_price_no_older_than(clock, slots)?, secs)
+            }
+        };
+
+        if price.publish_time.abs_diff(clock.unix_timestamp) > max_age {
+            return None;
+        }
+
+        Some(price)
+    }
+
+    /// Get the last valid price as long as it was published at or after `min_publish_time`.
+    ///
+    /// This is a wall-clock alternative to `get_price_no_older_than`. The price and publish time
+    /// are the same as the ones reported by `to_price_feed`, i.e., the previous price is used if
+    /// the aggregate is not currently trading.
+    pub fn get_price_newer_than_timestamp(&self, min_publish_time: UnixTimestamp) -> Option<Price> {
+        let price = self.get_current_price();
+
+        if price.publish_time < min_publish_time {
+            return None;
+        }
+
+        Some(price)
+    }
+
+    /// Get the change of the aggregate price since the previous aggregate with TRADING status,
+    /// i.e., `agg.price - prev_price`, scaled to `result_expo`.
+    ///
+    /// The confidence of the result is `agg.conf + prev_conf`. Returns `None` if the result cannot
+    /// be represented with the requested exponent.
+    pub fn jump_since_prev(&self, result_expo: i32) -> Option<Price> {
+        let agg_price = Price {
+            conf:         self.agg.conf,
+            expo:         self.expo,
+            price:        self.agg.price,
+            publish_time: self.timestamp,
+        };
+        let prev_price = Price {
+            conf:         self.prev_conf,
+            expo:         self.expo,
+            price:        self.prev_price,
+            publish_time: self.prev_timestamp,
+        };
+
+        (agg_price - prev_price)?.scale_to_exponent(result_expo)
+    }
+
+    /// Estimate the duration of a slot in seconds from the gap between the previous and the current
+    /// aggregate, i.e., `(timestamp - prev_timestamp) / (agg.pub_slot - prev_slot)`.
+    ///
+    /// This can be used to reconcile slot-based and time-based freshness checks. Returns `None` if
+    /// the current aggregate is not newer than the previous one in both slots and time.
+    pub fn estimated_slot_duration_secs(&self) -> Option<f64> {
+        let slot_diff = self.agg.pub_slot.checked_sub(self.prev_slot)?;
+        let time_diff = self.timestamp.checked_sub(self.prev_timestamp)?;
+
+        if slot_diff == 0 || time_diff <= 0 {
+            return None;
+        }
+
+        Some(time_diff as f64 / slot_diff as f64)
+    }
+
+    #[cfg(feature = "solana-program")]
     pub fn to_price_feed(&self, price_key: &Pubkey) -> PriceFeed {
-        let status = self.agg.status;
+        self.price_feed_with_id(crate::identifier_from_pubkey(price_key))
+    }
+
+    /// Same as `to_price_feed`, but takes the id of the feed rather than the key of the account.
+    pub(crate) fn price_feed_with_id(&self, id: PriceIdentifier) -> PriceFeed {
+        let price = self.get_current_price();
+
+        let ema_price = Price {
+            conf:         self.ema_conf.val as u64,
+            expo:         self.expo,
+            price:        self.ema_price.val,
+            publish_time: self.get_publish_time(),
+        };
+
+        PriceFeed::new(id, price, ema_price)
+    }
+
+    /// Same as `to_price_feed`, but also records the slot in which the price of the feed was
+    /// published, i.e., the slot of the aggregate price if it is trading and the previous slot
+    /// otherwise.
+    #[cfg(feature = "solana-program")]
+    pub fn to_price_feed_with_slot(&self, price_key: &Pubkey) -> PriceFeedWithSlot {
+        let pub_slot = match self.agg.status {
+            PriceStatus::Trading => self.agg.pub_slot,
+            _ => self.prev_slot,
+        };
+
+        PriceFeedWithSlot::new(self.to_price_feed(price_key), pub_slot)
+    }
+
+    /// Check whether the confidence interval of the price reported by `to_price_feed` includes
+    /// zero. See `Price::band_crosses_zero`.
+    pub fn band_crosses_zero(&self) -> bool {
+        self.get_current_price().band_crosses_zero()
+    }
+
+    /// Same as `to_price_feed`, but also reports whether the price of the feed is the current
+    /// aggregate or the fallback previous price.
+    #[cfg(feature = "solana-program")]
+    pub fn to_price_feed_with_source(&self, price_key: &Pubkey) -> (PriceFeed, PriceSource) {
+        let source = match self.agg.status {
+            PriceStatus::Trading => PriceSource::Aggregate,
+            _ => PriceSource::Previous,
+        };
+
+        (self.to_price_feed(price_key), source)
+    }
+
+    /// Same as `to_price_feed`, but returns `None` if the status of the aggregate price is
+    /// `Unknown` and the account has no valid previous price, i.e., `prev_slot` is zero.
+    ///
+    /// `to_price_feed` falls back to the previous price for every status other than `Trading`,
+    /// which can yield a zero price for an outage. `Halted` and `Auction` prices still fall back
+    /// to the previous price, as these statuses are intentional.
+    #[cfg(feature = "solana-program")]
+    pub fn to_price_feed_strict(&self, price_key: &Pubkey) -> Option<PriceFeed> {
+        if self.agg.status == PriceStatus::Unknown && self.prev_slot == 0 {
+            return None;
+        }
 
-        let price = match status {
+        Some(self.to_price_feed(price_key))
+    }
+
+    /// Get the aggregate price if it is trading, and the previous price otherwise.
+    fn get_current_price(&self) -> Price {
+        match self.agg.status {
             PriceStatus::Trading => Price {
                 conf:         self.agg.conf,
                 expo:         self.expo,
@@ -485,36 +996,45 @@ where
                 price:        self.prev_price,
                 publish_time: self.get_publish_time(),
             },
-        };
+        }
+    }
+}
 
-        let ema_price = Price {
-            conf:         self.ema_conf.val as u64,
-            expo:         self.expo,
-            price:        self.ema_price.val,
-            publish_time: self.get_publish_time(),
-        };
+/// Median of `values`, using the mean of the two middle values (rounded towards zero) for an even
+/// number of values. Returns `None` if `values` is empty.
+fn median(mut values: Vec<i128>) -> Option<i128> {
+    values.sort_unstable();
 
-        PriceFeed::new(PriceIdentifier::new(price_key.to_bytes()), price, ema_price)
+    let mid = values.len() / 2;
+    match values.len() {
+        0 => None,
+        len if len % 2 == 1 => Some(values[mid]),
+        _ => Some((values[mid - 1] + values[mid]) / 2),
     }
 }
 
-fn load<T: Pod>(data: &[u8]) -> Result<&T, PodCastError> {
+fn load<T: Pod>(data: &[u8]) -> Result<&T, PythError> {
     let size = size_of::<T>();
     if data.len() >= size {
-        Ok(from_bytes(cast_slice::<u8, u8>(try_cast_slice(
-            &data[0..size],
-        )?)))
+        Ok(from_bytes(cast_slice::<u8, u8>(
+            try_cast_slice(&data[0..size]).map_err(|_| PythError::InvalidAccountData)?,
+        )))
     } else {
-        Err(PodCastError::SizeMismatch)
+        Err(PythError::AccountTooSmall {
+            expected: size,
+            actual:   data.len(),
+        })
     }
 }
 
 /// Get a `Mapping` account from the raw byte value of a Solana account.
 pub fn load_mapping_account(data: &[u8]) -> Result<&MappingAccount, PythError> {
-    let pyth_mapping = load::<MappingAccount>(data).map_err(|_| PythError::InvalidAccountData)?;
+    let pyth_mapping = load::<MappingAccount>(data)?;
 
     if pyth_mapping.magic != MAGIC {
-        return Err(PythError::InvalidAccountData);
+        return Err(PythError::WrongMagic {
+            found: pyth_mapping.magic,
+        });
     }
     if pyth_mapping.ver != VERSION_2 {
         return Err(PythError::BadVersionNumber);
@@ -528,10 +1048,12 @@ pub fn load_mapping_account(data: &[u8]) -> Result<&MappingAccount, PythError> {
 
 /// Get a `Product` account from the raw byte value of a Solana account.
 pub fn load_product_account(data: &[u8]) -> Result<&ProductAccount, PythError> {
-    let pyth_product = load::<ProductAccount>(data).map_err(|_| PythError::InvalidAccountData)?;
+    let pyth_product = load::<ProductAccount>(data)?;
 
     if pyth_product.magic != MAGIC {
-        return Err(PythError::InvalidAccountData);
+        return Err(PythError::WrongMagic {
+            found: pyth_product.magic,
+        });
     }
     if pyth_product.ver != VERSION_2 {
         return Err(PythError::BadVersionNumber);
@@ -547,13 +1069,26 @@ pub fn load_product_account(data: &[u8]) -> Result<&ProductAccount, PythError> {
 pub fn load_price_account<const N: usize, T: Default + Copy + 'static>(
     data: &[u8],
 ) -> Result<&GenericPriceAccount<N, T>, PythError> {
-    let pyth_price =
-        load::<GenericPriceAccount<N, T>>(data).map_err(|_| PythError::InvalidAccountData)?;
+    load_price_account_versioned(data, &[VERSION_2])
+}
+
+/// Same as `load_price_account`, but accepts any account version in `accepted` rather than only
+/// `VERSION_2`.
+///
+/// This allows opting into future account versions whose layout is compatible with the current
+/// one without waiting for a new release of this crate.
+pub fn load_price_account_versioned<'a, const N: usize, T: Default + Copy + 'static>(
+    data: &'a [u8],
+    accepted: &[u32],
+) -> Result<&'a GenericPriceAccount<N, T>, PythError> {
+    let pyth_price = load::<GenericPriceAccount<N, T>>(data)?;
 
     if pyth_price.magic != MAGIC {
-        return Err(PythError::InvalidAccountData);
+        return Err(PythError::WrongMagic {
+            found: pyth_price.magic,
+        });
     }
-    if pyth_price.ver != VERSION_2 {
+    if !accepted.contains(&pyth_price.ver) {
         return Err(PythError::BadVersionNumber);
     }
     if pyth_price.atype != AccountType::Price as u32 {
@@ -563,6 +1098,50 @@ pub fn load_price_account<const N: usize, T: Default + Copy + 'static>(
     Ok(pyth_price)
 }
 
+/// Get a `Price` account from the raw byte value of a Solana account, detecting whether it uses
+/// the Solana or the Pythnet layout.
+///
+/// The layout is chosen based on the `size` field of the account: the Pythnet layout is used if the
+/// account is at least as large as a `PythnetPriceAccount` and can be loaded as such. Otherwise,
+/// the data is loaded as a `SolanaPriceAccount`.
+pub fn load_price_account_any(data: &[u8]) -> Result<ParsedPriceAccount, PythError> {
+    let size = read_u32(data, 12).unwrap_or_default();
+
+    if size as usize >= size_of::<PythnetPriceAccount>() {
+        if let Ok(price_account) = load_price_account(data) {
+            return Ok(ParsedPriceAccount::Pythnet(price_account));
+        }
+    }
+
+    load_price_account(data).map(ParsedPriceAccount::Solana)
+}
+
+/// Inspect the raw byte value of a Solana account to find out why `load_price_account` would
+/// reject it. Unlike the loading functions, this never fails, e.g., for use in debugging tools.
+pub fn diagnose_price_account(data: &[u8]) -> PriceAccountDiagnosis {
+    let component_count = if data.len() >= size_of::<PythnetPriceAccount>() {
+        Some(128)
+    } else if data.len() >= size_of::<SolanaPriceAccount>() {
+        Some(32)
+    } else {
+        None
+    };
+
+    PriceAccountDiagnosis {
+        length_ok: component_count.is_some(),
+        magic: read_u32(data, 0),
+        version: read_u32(data, 4),
+        account_type: read_u32(data, 8),
+        component_count,
+    }
+}
+
+/// Read the little-endian `u32` at `offset` of `data`, if `data` is long enough.
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
 pub struct AttributeIter<'a> {
     attrs: &'a [u8],
 }
@@ -591,7 +1170,7 @@ fn get_attr_str(buf: &[u8]) -> (&str, &[u8]) {
     (str, remaining_buf)
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "solana-program"))]
 mod test {
     use pyth_sdk::{
         Identifier,
@@ -602,11 +1181,35 @@ mod test {
     use solana_program::pubkey::Pubkey;
 
     use super::{
+        diagnose_price_account,
+        load_mapping_account,
+        load_price_account,
+        load_price_account_any,
+        load_product_account,
+        AccountType,
+        CorpAction,
+        FreshnessPolicy,
+        MappingAccount,
+        ParsedPriceAccount,
+        PriceAccountDiagnosis,
+        PriceAccountExt,
+        PriceComp,
+        PriceCumulative,
         PriceInfo,
+        PriceSource,
         PriceStatus,
+        ProductAccount,
+        PythnetPriceAccount,
         Rational,
         SolanaPriceAccount,
+        MAGIC,
+        MAP_TABLE_SIZE,
+        PROD_ACCT_SIZE,
+        PROD_ATTR_SIZE,
+        PROD_HDR_SIZE,
+        VERSION_2,
     };
+    use crate::PythError;
 
     #[test]
     fn test_trading_price_to_price_feed() {
@@ -829,62 +1432,162 @@ mod test {
     }
 
     #[test]
-    fn test_price_feed_representations_equal() {
-        #[repr(C)]
-        #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
-        pub struct OldPriceAccount {
-            pub magic:          u32,
-            pub ver:            u32,
-            pub atype:          u32,
-            pub size:           u32,
-            pub ptype:          crate::state::PriceType,
-            pub expo:           i32,
-            pub num:            u32,
-            pub num_qt:         u32,
-            pub last_slot:      u64,
-            pub valid_slot:     u64,
-            pub ema_price:      Rational,
-            pub ema_conf:       Rational,
-            pub timestamp:      i64,
-            pub min_pub:        u8,
-            pub drv2:           u8,
-            pub drv3:           u16,
-            pub drv4:           u32,
-            pub prod:           Pubkey,
-            pub next:           Pubkey,
-            pub prev_slot:      u64,
-            pub prev_price:     i64,
-            pub prev_conf:      u64,
-            pub prev_timestamp: i64,
-            pub agg:            PriceInfo,
-            pub comp:           [crate::state::PriceComp; 32],
-        }
-
-        // Would be better to fuzz this but better than no check.
-        let old = OldPriceAccount {
-            magic:          1,
-            ver:            2,
-            atype:          3,
-            size:           4,
-            ptype:          crate::state::PriceType::Price,
-            expo:           5,
-            num:            6,
-            num_qt:         7,
-            last_slot:      8,
-            valid_slot:     9,
-            ema_price:      Rational {
-                val:   1,
-                numer: 2,
-                denom: 3,
-            },
-            ema_conf:       Rational {
-                val:   1,
-                numer: 2,
-                denom: 3,
+    fn test_price_no_older_than_threshold_above_slot() {
+        let mut price_account = SolanaPriceAccount {
+            expo: 5,
+            agg: PriceInfo {
+                price: 10,
+                conf: 20,
+                status: PriceStatus::Unknown,
+                pub_slot: 3,
+                ..Default::default()
             },
-            timestamp:      12,
-            min_pub:        13,
-            drv2:           14,
+            timestamp: 200,
+            prev_timestamp: 100,
+            prev_price: 60,
+            prev_conf: 70,
+            prev_slot: 1,
+            ..Default::default()
+        };
+
+        let clock = Clock {
+            slot: 5,
+            ..Default::default()
+        };
+
+        // slot - slot_threshold would underflow; every slot since genesis is within the threshold.
+        assert_eq!(
+            price_account.get_price_no_older_than(&clock, 100),
+            Some(Price {
+                conf:         70,
+                expo:         5,
+                price:        60,
+                publish_time: 100,
+            })
+        );
+
+        price_account.agg.status = PriceStatus::Trading;
+        assert_eq!(
+            price_account.get_price_no_older_than(&clock, 100),
+            Some(Price {
+                conf:         20,
+                expo:         5,
+                price:        10,
+                publish_time: 200,
+            })
+        );
+    }
+
+    #[test]
+    fn test_price_no_older_than_min_pub() {
+        let price_account = SolanaPriceAccount {
+            expo: 5,
+            num_qt: 2,
+            agg: PriceInfo {
+                price: 10,
+                conf: 20,
+                status: PriceStatus::Trading,
+                pub_slot: 1000,
+                ..Default::default()
+            },
+            timestamp: 200,
+            ..Default::default()
+        };
+        let clock = Clock {
+            slot: 1010,
+            ..Default::default()
+        };
+        let price = Price {
+            conf:         20,
+            expo:         5,
+            price:        10,
+            publish_time: 200,
+        };
+
+        // Fresh with enough quoters
+        assert_eq!(
+            price_account.get_price_no_older_than_min_pub(&clock, 25, 2),
+            Some(price)
+        );
+        assert_eq!(
+            price_account.get_price_no_older_than_min_pub(&clock, 25, 0),
+            Some(price)
+        );
+
+        // Fresh with too few quoters
+        assert_eq!(
+            price_account.get_price_no_older_than_min_pub(&clock, 25, 3),
+            None
+        );
+        assert_eq!(
+            price_account.get_price_no_older_than_min_pub(&clock, 25, u32::MAX),
+            None
+        );
+
+        // Stale with enough quoters
+        assert_eq!(
+            price_account.get_price_no_older_than_min_pub(&clock, 5, 2),
+            None
+        );
+    }
+
+    #[test]
+    fn test_price_feed_representations_equal() {
+        #[repr(C)]
+        #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+        pub struct OldPriceAccount {
+            pub magic:          u32,
+            pub ver:            u32,
+            pub atype:          u32,
+            pub size:           u32,
+            pub ptype:          crate::state::PriceType,
+            pub expo:           i32,
+            pub num:            u32,
+            pub num_qt:         u32,
+            pub last_slot:      u64,
+            pub valid_slot:     u64,
+            pub ema_price:      Rational,
+            pub ema_conf:       Rational,
+            pub timestamp:      i64,
+            pub min_pub:        u8,
+            pub drv2:           u8,
+            pub drv3:           u16,
+            pub drv4:           u32,
+            pub prod:           Pubkey,
+            pub next:           Pubkey,
+            pub prev_slot:      u64,
+            pub prev_price:     i64,
+            pub prev_conf:      u64,
+            pub prev_timestamp: i64,
+            pub agg:            PriceInfo,
+            pub comp:           [crate::state::PriceComp; 32],
+        }
+
+        // Would be better to fuzz this but better than no check.
+        let old = OldPriceAccount {
+            magic:          1,
+            ver:            2,
+            atype:          3,
+            size:           4,
+            ptype:          crate::state::PriceType::Price,
+            expo:           5,
+            num:            6,
+            num_qt:         7,
+            last_slot:      8,
+            valid_slot:     9,
+            ema_price:      Rational {
+                val:   1,
+                numer: 2,
+                denom: 3,
+            },
+            ema_conf:       Rational {
+                val:   1,
+                numer: 2,
+                denom: 3,
+            },
+            timestamp:      12,
+            min_pub:        13,
+            drv2:           14,
             drv3:           15,
             drv4:           16,
             prod:           Pubkey::new_from_array([1; 32]),
@@ -965,4 +1668,1404 @@ mod test {
             assert_eq!(old_b, new_b);
         }
     }
+
+    #[test]
+    fn test_price_newer_than_timestamp() {
+        let mut price_account = SolanaPriceAccount {
+            expo: 5,
+            agg: PriceInfo {
+                price: 10,
+                conf: 20,
+                status: PriceStatus::Trading,
+                ..Default::default()
+            },
+            timestamp: 200,
+            prev_timestamp: 100,
+            prev_price: 60,
+            prev_conf: 70,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            price_account.get_price_newer_than_timestamp(200),
+            Some(Price {
+                conf:         20,
+                expo:         5,
+                price:        10,
+                publish_time: 200,
+            })
+        );
+        assert_eq!(price_account.get_price_newer_than_timestamp(201), None);
+
+        // Not trading, so the previous price and its timestamp are used.
+        price_account.agg.status = PriceStatus::Unknown;
+        assert_eq!(
+            price_account.get_price_newer_than_timestamp(100),
+            Some(Price {
+                conf:         70,
+                expo:         5,
+                price:        60,
+                publish_time: 100,
+            })
+        );
+        assert_eq!(price_account.get_price_newer_than_timestamp(101), None);
+    }
+
+    #[test]
+    fn test_jump_since_prev() {
+        let price_account = SolanaPriceAccount {
+            expo: -2,
+            agg: PriceInfo {
+                price: 1050,
+                conf: 20,
+                status: PriceStatus::Trading,
+                ..Default::default()
+            },
+            timestamp: 200,
+            prev_timestamp: 100,
+            prev_price: 1000,
+            prev_conf: 10,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            price_account.jump_since_prev(-2),
+            Some(Price {
+                conf:         30,
+                expo:         -2,
+                price:        50,
+                publish_time: 100,
+            })
+        );
+        assert_eq!(
+            price_account.jump_since_prev(-3),
+            Some(Price {
+                conf:         300,
+                expo:         -3,
+                price:        500,
+                publish_time: 100,
+            })
+        );
+
+        let price_drop_account = SolanaPriceAccount {
+            prev_price: 1100,
+            ..price_account
+        };
+        assert_eq!(
+            price_drop_account.jump_since_prev(-2),
+            Some(Price {
+                conf:         30,
+                expo:         -2,
+                price:        -50,
+                publish_time: 100,
+            })
+        );
+
+        let overflow_account = SolanaPriceAccount {
+            prev_price: i64::MIN,
+            ..price_account
+        };
+        assert_eq!(overflow_account.jump_since_prev(-2), None);
+    }
+
+    #[test]
+    fn test_price_with_policy() {
+        let price_account = SolanaPriceAccount {
+            expo: 5,
+            agg: PriceInfo {
+                price: 10,
+                conf: 20,
+                status: PriceStatus::Trading,
+                pub_slot: 3,
+                ..Default::default()
+            },
+            timestamp: 200,
+            prev_timestamp: 100,
+            prev_price: 60,
+            prev_conf: 70,
+            prev_slot: 1,
+            ..Default::default()
+        };
+        let price = Some(Price {
+            conf:         20,
+            expo:         5,
+            price:        10,
+            publish_time: 200,
+        });
+
+        let clock = Clock {
+            slot: 5,
+            unix_timestamp: 210,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            price_account.get_price_with_policy(&clock, FreshnessPolicy::Slots(2)),
+            price
+        );
+        assert_eq!(
+            price_account.get_price_with_policy(&clock, FreshnessPolicy::Slots(1)),
+            None
+        );
+
+        assert_eq!(
+            price_account.get_price_with_policy(&clock, FreshnessPolicy::Seconds(10)),
+            price
+        );
+        assert_eq!(
+            price_account.get_price_with_policy(&clock, FreshnessPolicy::Seconds(9)),
+            None
+        );
+
+        assert_eq!(
+            price_account.get_price_with_policy(
+                &clock,
+                FreshnessPolicy::Both {
+                    slots: 2,
+                    secs:  10,
+                }
+            ),
+            price
+        );
+        assert_eq!(
+            price_account.get_price_with_policy(
+                &clock,
+                FreshnessPolicy::Both {
+                    slots: 1,
+                    secs:  10,
+                }
+            ),
+            None
+        );
+        assert_eq!(
+            price_account
+                .get_price_with_policy(&clock, FreshnessPolicy::Both { slots: 2, secs: 9 }),
+            None
+        );
+
+        // Extreme timestamps don't overflow
+        let extreme_account = SolanaPriceAccount {
+            timestamp: i64::MAX,
+            ..price_account
+        };
+        let extreme_clock = Clock {
+            unix_timestamp: i64::MIN,
+            ..clock
+        };
+        assert_eq!(
+            extreme_account.get_price_with_policy(&extreme_clock, FreshnessPolicy::Seconds(10)),
+            None
+        );
+        assert_eq!(
+            extreme_account
+                .get_price_with_policy(&extreme_clock, FreshnessPolicy::Seconds(u64::MAX)),
+            price.map(|price| Price {
+                publish_time: i64::MAX,
+                ..price
+            })
+        );
+        let extreme_account = SolanaPriceAccount {
+            timestamp: i64::MIN,
+            ..price_account
+        };
+        let extreme_clock = Clock {
+            unix_timestamp: i64::MAX,
+            ..clock
+        };
+        assert_eq!(
+            extreme_account.get_price_with_policy(&extreme_clock, FreshnessPolicy::Seconds(10)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_price_feed_with_source() {
+        let mut price_account = SolanaPriceAccount {
+            expo: 5,
+            agg: PriceInfo {
+                price: 10,
+                conf: 20,
+                status: PriceStatus::Trading,
+                ..Default::default()
+            },
+            timestamp: 200,
+            prev_timestamp: 100,
+            prev_price: 60,
+            prev_conf: 70,
+            ..Default::default()
+        };
+        let pubkey = Pubkey::new_from_array([3; 32]);
+
+        let (price_feed, source) = price_account.to_price_feed_with_source(&pubkey);
+        assert_eq!(price_feed, price_account.to_price_feed(&pubkey));
+        assert_eq!(price_feed.get_price_unchecked().price, 10);
+        assert_eq!(source, PriceSource::Aggregate);
+
+        price_account.agg.status = PriceStatus::Halted;
+        let (price_feed, source) = price_account.to_price_feed_with_source(&pubkey);
+        assert_eq!(price_feed, price_account.to_price_feed(&pubkey));
+        assert_eq!(price_feed.get_price_unchecked().price, 60);
+        assert_eq!(source, PriceSource::Previous);
+    }
+
+    #[test]
+    fn test_to_price_feed_strict() {
+        let pubkey = Pubkey::new_from_array([3; 32]);
+
+        // Unknown status without a previous price
+        let price_account = SolanaPriceAccount {
+            expo: 5,
+            agg: PriceInfo {
+                price: 10,
+                conf: 20,
+                status: PriceStatus::Unknown,
+                pub_slot: 1000,
+                ..Default::default()
+            },
+            timestamp: 200,
+            ..Default::default()
+        };
+        assert_eq!(price_account.to_price_feed_strict(&pubkey), None);
+
+        // Unknown status with a previous price
+        let price_account = SolanaPriceAccount {
+            prev_slot: 900,
+            prev_timestamp: 100,
+            prev_price: 60,
+            prev_conf: 70,
+            ..price_account
+        };
+        assert_eq!(
+            price_account.to_price_feed_strict(&pubkey),
+            Some(price_account.to_price_feed(&pubkey))
+        );
+
+        // Halted status with a previous price
+        let price_account = SolanaPriceAccount {
+            agg: PriceInfo {
+                status: PriceStatus::Halted,
+                ..price_account.agg
+            },
+            ..price_account
+        };
+        assert_eq!(
+            price_account
+                .to_price_feed_strict(&pubkey)
+                .map(|feed| feed.get_price_unchecked()),
+            Some(Price {
+                price:        60,
+                conf:         70,
+                expo:         5,
+                publish_time: 100,
+            })
+        );
+
+        // Trading status
+        let price_account = SolanaPriceAccount {
+            agg: PriceInfo {
+                status: PriceStatus::Trading,
+                ..price_account.agg
+            },
+            ..price_account
+        };
+        assert_eq!(
+            price_account.to_price_feed_strict(&pubkey),
+            Some(price_account.to_price_feed(&pubkey))
+        );
+    }
+
+    #[test]
+    fn test_price_feed_with_slot() {
+        let mut price_account = SolanaPriceAccount {
+            expo: 5,
+            agg: PriceInfo {
+                price: 10,
+                conf: 20,
+                status: PriceStatus::Trading,
+                pub_slot: 1000,
+                ..Default::default()
+            },
+            timestamp: 200,
+            prev_slot: 900,
+            prev_timestamp: 100,
+            prev_price: 60,
+            prev_conf: 70,
+            ..Default::default()
+        };
+        let pubkey = Pubkey::new_from_array([3; 32]);
+
+        let feed = price_account.to_price_feed_with_slot(&pubkey);
+        assert_eq!(feed.price_feed, price_account.to_price_feed(&pubkey));
+        assert_eq!(feed.pub_slot, 1000);
+
+        // Within the threshold
+        assert_eq!(
+            feed.get_price_no_older_than_slots(1000, 0),
+            Some(feed.price_feed.get_price_unchecked())
+        );
+        assert_eq!(
+            feed.get_price_no_older_than_slots(1025, 25),
+            Some(feed.price_feed.get_price_unchecked())
+        );
+        // A current slot before the publish slot is not stale
+        assert_eq!(
+            feed.get_price_no_older_than_slots(999, 0),
+            Some(feed.price_feed.get_price_unchecked())
+        );
+
+        // Stale by slots
+        assert_eq!(feed.get_price_no_older_than_slots(1026, 25), None);
+        assert_eq!(feed.get_price_no_older_than_slots(u64::MAX, 25), None);
+
+        // The previous slot is used when the aggregate isn't trading
+        price_account.agg.status = PriceStatus::Halted;
+        let feed = price_account.to_price_feed_with_slot(&pubkey);
+        assert_eq!(feed.pub_slot, 900);
+        assert_eq!(feed.price_feed.get_price_unchecked().price, 60);
+        assert_eq!(feed.get_price_no_older_than_slots(1000, 25), None);
+        assert_eq!(
+            feed.get_price_no_older_than_slots(925, 25),
+            Some(feed.price_feed.get_price_unchecked())
+        );
+    }
+
+    #[test]
+    fn test_band_crosses_zero() {
+        let mut price_account = SolanaPriceAccount {
+            agg: PriceInfo {
+                price: 10,
+                conf: 20,
+                status: PriceStatus::Trading,
+                ..Default::default()
+            },
+            prev_price: 60,
+            prev_conf: 50,
+            ..Default::default()
+        };
+        assert!(price_account.band_crosses_zero());
+
+        // Not trading, so the previous price is used.
+        price_account.agg.status = PriceStatus::Unknown;
+        assert!(!price_account.band_crosses_zero());
+    }
+
+    #[test]
+    fn test_price_comp_prices() {
+        let mut comp = [PriceComp::default(); 32];
+        comp[0] = PriceComp {
+            publisher: Pubkey::new_from_array([1; 32]),
+            agg:       PriceInfo {
+                price: 100,
+                conf: 2,
+                status: PriceStatus::Trading,
+                pub_slot: 7,
+                ..Default::default()
+            },
+            latest:    PriceInfo {
+                price: 105,
+                conf: 3,
+                status: PriceStatus::Trading,
+                pub_slot: 8,
+                ..Default::default()
+            },
+        };
+        let price_account = SolanaPriceAccount {
+            expo: -5,
+            num: 1,
+            comp,
+            ..Default::default()
+        };
+
+        let comp = &price_account.comp[0];
+        assert_eq!(
+            comp.agg_price(price_account.expo),
+            Price {
+                conf:         2,
+                expo:         -5,
+                price:        100,
+                publish_time: 0,
+            }
+        );
+        assert_eq!(
+            comp.latest_price(price_account.expo),
+            Price {
+                conf:         3,
+                expo:         -5,
+                price:        105,
+                publish_time: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_estimated_slot_duration_secs() {
+        let price_account = SolanaPriceAccount {
+            agg: PriceInfo {
+                pub_slot: 1_250,
+                ..Default::default()
+            },
+            timestamp: 1_500,
+            prev_slot: 1_000,
+            prev_timestamp: 1_400,
+            ..Default::default()
+        };
+        assert_eq!(price_account.estimated_slot_duration_secs(), Some(0.4));
+
+        let price_account = SolanaPriceAccount {
+            agg: PriceInfo {
+                pub_slot: 12,
+                ..Default::default()
+            },
+            timestamp: 106,
+            prev_slot: 10,
+            prev_timestamp: 100,
+            ..Default::default()
+        };
+        assert_eq!(price_account.estimated_slot_duration_secs(), Some(3.0));
+
+        // Same slot
+        let price_account = SolanaPriceAccount {
+            agg: PriceInfo {
+                pub_slot: 10,
+                ..Default::default()
+            },
+            timestamp: 106,
+            prev_slot: 10,
+            prev_timestamp: 100,
+            ..Default::default()
+        };
+        assert_eq!(price_account.estimated_slot_duration_secs(), None);
+
+        // Previous slot is newer than the current one
+        let price_account = SolanaPriceAccount {
+            agg: PriceInfo {
+                pub_slot: 9,
+                ..Default::default()
+            },
+            timestamp: 106,
+            prev_slot: 10,
+            prev_timestamp: 100,
+            ..Default::default()
+        };
+        assert_eq!(price_account.estimated_slot_duration_secs(), None);
+
+        // Previous timestamp is newer than the current one
+        let price_account = SolanaPriceAccount {
+            agg: PriceInfo {
+                pub_slot: 12,
+                ..Default::default()
+            },
+            timestamp: 99,
+            prev_slot: 10,
+            prev_timestamp: 100,
+            ..Default::default()
+        };
+        assert_eq!(price_account.estimated_slot_duration_secs(), None);
+
+        // Same timestamp
+        let price_account = SolanaPriceAccount {
+            agg: PriceInfo {
+                pub_slot: 12,
+                ..Default::default()
+            },
+            timestamp: 100,
+            prev_slot: 10,
+            prev_timestamp: 100,
+            ..Default::default()
+        };
+        assert_eq!(price_account.estimated_slot_duration_secs(), None);
+    }
+
+    #[test]
+    fn test_iter_price_comps() {
+        let mut comp = [PriceComp::default(); 32];
+        for (i, c) in comp.iter_mut().enumerate().take(3) {
+            c.publisher = Pubkey::new_from_array([i as u8 + 1; 32]);
+            c.agg.price = 100 + i as i64;
+        }
+        let mut price_account = SolanaPriceAccount {
+            num: 3,
+            comp,
+            ..Default::default()
+        };
+
+        let comps: Vec<&PriceComp> = price_account.iter_price_comps().collect();
+        assert_eq!(comps.len(), 3);
+        for (i, c) in comps.iter().enumerate() {
+            assert_eq!(c.publisher, Pubkey::new_from_array([i as u8 + 1; 32]));
+            assert_eq!(c.agg.price, 100 + i as i64);
+        }
+
+        price_account.num = 0;
+        assert_eq!(price_account.iter_price_comps().count(), 0);
+
+        // `num` is clamped to the size of `comp`.
+        price_account.num = 64;
+        assert_eq!(price_account.iter_price_comps().count(), 32);
+    }
+
+    #[test]
+    fn test_compute_component_median() {
+        let mut comp = [PriceComp::default(); 32];
+        let aggs = [
+            (100, 4, PriceStatus::Trading),
+            (90, 1, PriceStatus::Trading),
+            (1_000, 100, PriceStatus::Unknown),
+            (120, 3, PriceStatus::Trading),
+            (95, 10, PriceStatus::Trading),
+        ];
+        for (c, (price, conf, status)) in comp.iter_mut().zip(aggs.iter()) {
+            c.agg = PriceInfo {
+                price: *price,
+                conf: *conf,
+                status: *status,
+                ..Default::default()
+            };
+        }
+        // An unpopulated trading slot beyond `num` must be ignored.
+        comp[5].agg = PriceInfo {
+            price: 1,
+            conf: 1,
+            status: PriceStatus::Trading,
+            ..Default::default()
+        };
+        let mut price_account = SolanaPriceAccount {
+            expo: -2,
+            num: 5,
+            min_pub: 3,
+            timestamp: 200,
+            comp,
+            ..Default::default()
+        };
+
+        // Even count of trading components: 90, 95, 100, 120 and 1, 3, 4, 10
+        assert_eq!(
+            price_account.compute_component_median(),
+            Some(Price {
+                conf:         3,
+                expo:         -2,
+                price:        97,
+                publish_time: 200,
+            })
+        );
+
+        // Odd count of trading components: 90, 100, 120 and 1, 3, 4
+        price_account.comp[4].agg.status = PriceStatus::Halted;
+        assert_eq!(
+            price_account.compute_component_median(),
+            Some(Price {
+                conf:         3,
+                expo:         -2,
+                price:        100,
+                publish_time: 200,
+            })
+        );
+
+        // Fewer trading components than `min_pub`
+        price_account.min_pub = 4;
+        assert_eq!(price_account.compute_component_median(), None);
+
+        // No trading components
+        price_account.min_pub = 0;
+        price_account.num = 0;
+        assert_eq!(price_account.compute_component_median(), None);
+    }
+
+    #[test]
+    fn test_aggregate_info() {
+        let price_account = SolanaPriceAccount {
+            agg: PriceInfo {
+                price:    10,
+                conf:     20,
+                status:   PriceStatus::Halted,
+                corp_act: CorpAction::NoCorpAct,
+                pub_slot: 30,
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(price_account.aggregate_info(), price_account.agg);
+    }
+
+    #[test]
+    fn test_num_publishers_and_num_trading() {
+        let mut comp = [PriceComp::default(); 32];
+        comp[0].agg.status = PriceStatus::Trading;
+        comp[1].agg.status = PriceStatus::Halted;
+        comp[2].agg.status = PriceStatus::Trading;
+        comp[3].agg.status = PriceStatus::Unknown;
+        // Beyond `num`
+        comp[4].agg.status = PriceStatus::Trading;
+
+        let mut price_account = SolanaPriceAccount {
+            num: 4,
+            comp,
+            ..Default::default()
+        };
+        assert_eq!(price_account.num_publishers(), 4);
+        assert_eq!(price_account.num_trading(), 2);
+
+        price_account.comp[2].agg.status = PriceStatus::Halted;
+        assert_eq!(price_account.num_trading(), 1);
+
+        price_account.num = 100;
+        assert_eq!(price_account.num_publishers(), 32);
+        assert_eq!(price_account.num_trading(), 2);
+    }
+
+    #[test]
+    fn test_is_degenerate() {
+        let publisher = PriceComp {
+            publisher: Pubkey::new_from_array([7; 32]),
+            agg: PriceInfo {
+                price: 10,
+                conf: 1,
+                status: PriceStatus::Trading,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // Healthy account
+        let mut price_account = SolanaPriceAccount::builder()
+            .trading(10, 1, 1000)
+            .component(publisher)
+            .component(PriceComp {
+                publisher: Pubkey::new_from_array([8; 32]),
+                ..publisher
+            })
+            .build();
+        assert!(!price_account.is_degenerate());
+
+        // A single default publisher among real ones is not degenerate
+        price_account.comp[1].publisher = Pubkey::default();
+        assert!(!price_account.is_degenerate());
+
+        // No publishers
+        let price_account = SolanaPriceAccount::builder().build();
+        assert!(price_account.is_degenerate());
+
+        // Default publishers with unknown statuses, as seen in stale accounts. The system program
+        // id `11111111111111111111111111111111` is the all-zero key.
+        let default_publisher = PriceComp::default();
+        assert_eq!(default_publisher.agg.status, PriceStatus::Unknown);
+        let mut price_account = SolanaPriceAccount::builder()
+            .agg(PriceInfo::default())
+            .component(default_publisher)
+            .component(default_publisher)
+            .component(default_publisher)
+            .build();
+        assert!(price_account.is_degenerate());
+
+        // Real publishers beyond `num` are ignored
+        price_account.comp[3] = publisher;
+        assert!(price_account.is_degenerate());
+    }
+
+    fn product_account_with_attrs(attrs: &[(&str, &str)]) -> ProductAccount {
+        let mut attr = [0u8; PROD_ATTR_SIZE];
+        let mut len = 0;
+        for s in attrs.iter().flat_map(|(key, value)| [key, value]) {
+            attr[len] = s.len() as u8;
+            attr[len + 1..len + 1 + s.len()].copy_from_slice(s.as_bytes());
+            len += 1 + s.len();
+        }
+
+        ProductAccount {
+            magic: MAGIC,
+            ver: VERSION_2,
+            atype: AccountType::Product as u32,
+            size: (PROD_HDR_SIZE + len) as u32,
+            px_acc: Pubkey::default(),
+            attr,
+        }
+    }
+
+    #[test]
+    fn test_product_get_attribute() {
+        let product_account = product_account_with_attrs(&[
+            ("asset_type", "Crypto"),
+            ("base", "BTC"),
+            ("symbol", "Crypto.BTC/USD"),
+            ("base", "ETH"),
+        ]);
+
+        assert_eq!(product_account.get_attribute("asset_type"), Some("Crypto"));
+        assert_eq!(product_account.get_attribute("base"), Some("BTC"));
+        assert_eq!(product_account.get_attribute("quote_currency"), None);
+        assert_eq!(product_account.get_attribute(""), None);
+        assert_eq!(product_account.symbol(), Some("Crypto.BTC/USD"));
+
+        let product_account = product_account_with_attrs(&[("asset_type", "Crypto")]);
+        assert_eq!(product_account.symbol(), None);
+
+        let mut product_account = product_account_with_attrs(&[]);
+        assert_eq!(product_account.get_attribute("asset_type"), None);
+        assert_eq!(product_account.symbol(), None);
+
+        // Uninitialized size
+        product_account.size = 0;
+        assert_eq!(product_account.symbol(), None);
+    }
+
+    #[test]
+    fn test_product_attributes() {
+        let product_account = product_account_with_attrs(&[
+            ("asset_type", "Crypto"),
+            ("base", "BTC"),
+            ("symbol", "Crypto.BTC/USD"),
+            ("base", "ETH"),
+        ]);
+
+        let attributes = product_account.attributes();
+        assert_eq!(attributes.len(), 3);
+        assert_eq!(attributes.get("asset_type"), Some(&"Crypto"));
+        assert_eq!(attributes.get("symbol"), Some(&"Crypto.BTC/USD"));
+        assert_eq!(attributes.get("quote_currency"), None);
+
+        // The first value of a duplicate key is kept, like `get_attribute`
+        assert_eq!(attributes.get("base"), Some(&"BTC"));
+        assert_eq!(
+            attributes.get("base").copied(),
+            product_account.get_attribute("base")
+        );
+
+        // `iter` still yields every attribute
+        assert_eq!(product_account.iter().count(), 4);
+
+        let mut product_account = product_account_with_attrs(&[]);
+        assert!(product_account.attributes().is_empty());
+        product_account.size = 0;
+        assert!(product_account.attributes().is_empty());
+    }
+
+    #[test]
+    fn test_mapping_iter_products() {
+        let mut products = [Pubkey::default(); MAP_TABLE_SIZE];
+        for (i, product) in products.iter_mut().enumerate().take(3) {
+            *product = Pubkey::new_from_array([i as u8 + 1; 32]);
+        }
+        let mut mapping_account = MappingAccount {
+            magic: MAGIC,
+            ver: VERSION_2,
+            atype: AccountType::Mapping as u32,
+            size: 0,
+            num: 3,
+            unused: 0,
+            next: Pubkey::default(),
+            products,
+        };
+
+        assert_eq!(
+            mapping_account.iter_products().collect::<Vec<_>>(),
+            vec![
+                &Pubkey::new_from_array([1; 32]),
+                &Pubkey::new_from_array([2; 32]),
+                &Pubkey::new_from_array([3; 32]),
+            ]
+        );
+
+        mapping_account.num = 0;
+        assert_eq!(mapping_account.iter_products().count(), 0);
+
+        // `num` is clamped to the size of the table.
+        mapping_account.num = u32::MAX;
+        assert_eq!(mapping_account.iter_products().count(), MAP_TABLE_SIZE);
+    }
+
+    #[test]
+    fn test_load_price_account_any() {
+        let agg = PriceInfo {
+            price: 10,
+            conf: 20,
+            status: PriceStatus::Trading,
+            ..Default::default()
+        };
+        let solana_account = SolanaPriceAccount {
+            magic: MAGIC,
+            ver: VERSION_2,
+            atype: AccountType::Price as u32,
+            size: std::mem::size_of::<SolanaPriceAccount>() as u32,
+            expo: 5,
+            agg,
+            timestamp: 200,
+            ..Default::default()
+        };
+        let mut pythnet_account = PythnetPriceAccount {
+            magic: MAGIC,
+            ver: VERSION_2,
+            atype: AccountType::Price as u32,
+            size: std::mem::size_of::<PythnetPriceAccount>() as u32,
+            expo: 5,
+            agg,
+            timestamp: 200,
+            ..Default::default()
+        };
+        let key = Pubkey::new_from_array([1; 32]);
+
+        let parsed = load_price_account_any(bytemuck::bytes_of(&solana_account)).unwrap();
+        assert_eq!(parsed, ParsedPriceAccount::Solana(&solana_account));
+        assert_eq!(
+            parsed.to_price_feed(&key),
+            solana_account.to_price_feed(&key)
+        );
+
+        let parsed = load_price_account_any(bytemuck::bytes_of(&pythnet_account)).unwrap();
+        assert_eq!(parsed, ParsedPriceAccount::Pythnet(&pythnet_account));
+        assert_eq!(
+            parsed.to_price_feed(&key),
+            pythnet_account.to_price_feed(&key)
+        );
+
+        // A Pythnet account truncated to the Solana layout
+        let data = bytemuck::bytes_of(&pythnet_account);
+        let parsed =
+            load_price_account_any(&data[..std::mem::size_of::<SolanaPriceAccount>()]).unwrap();
+        assert!(matches!(parsed, ParsedPriceAccount::Solana(_)));
+
+        // A Solana account in a buffer large enough for the Pythnet layout
+        pythnet_account.size = std::mem::size_of::<SolanaPriceAccount>() as u32;
+        let parsed = load_price_account_any(bytemuck::bytes_of(&pythnet_account)).unwrap();
+        assert!(matches!(parsed, ParsedPriceAccount::Solana(_)));
+
+        assert_eq!(
+            load_price_account_any(&bytemuck::bytes_of(&solana_account)[..10]),
+            Err(PythError::AccountTooSmall {
+                expected: std::mem::size_of::<SolanaPriceAccount>(),
+                actual:   10,
+            })
+        );
+    }
+
+    #[test]
+    fn test_diagnose_price_account() {
+        let solana_account = SolanaPriceAccount {
+            magic: MAGIC,
+            ver: VERSION_2,
+            atype: AccountType::Price as u32,
+            ..Default::default()
+        };
+        let data = bytemuck::bytes_of(&solana_account);
+
+        let diagnosis = diagnose_price_account(data);
+        assert_eq!(
+            diagnosis,
+            PriceAccountDiagnosis {
+                length_ok:       true,
+                magic:           Some(MAGIC),
+                version:         Some(VERSION_2),
+                account_type:    Some(AccountType::Price as u32),
+                component_count: Some(32),
+            }
+        );
+        assert!(diagnosis.is_valid());
+
+        let pythnet_account = PythnetPriceAccount {
+            magic: MAGIC,
+            ver: VERSION_2,
+            atype: AccountType::Price as u32,
+            ..Default::default()
+        };
+        let diagnosis = diagnose_price_account(bytemuck::bytes_of(&pythnet_account));
+        assert_eq!(diagnosis.component_count, Some(128));
+        assert!(diagnosis.is_valid());
+
+        // Too short for any layout, but the header can still be read
+        let diagnosis = diagnose_price_account(&data[..100]);
+        assert!(!diagnosis.length_ok);
+        assert_eq!(diagnosis.magic, Some(MAGIC));
+        assert_eq!(diagnosis.component_count, None);
+        assert!(!diagnosis.is_valid());
+
+        // Too short for the header
+        assert_eq!(
+            diagnose_price_account(&data[..6]),
+            PriceAccountDiagnosis {
+                length_ok:       false,
+                magic:           Some(MAGIC),
+                version:         None,
+                account_type:    None,
+                component_count: None,
+            }
+        );
+        assert_eq!(
+            diagnose_price_account(&[]),
+            PriceAccountDiagnosis::default()
+        );
+
+        // Wrong magic, version and account type
+        let invalid_account = SolanaPriceAccount {
+            magic: 1,
+            ver: 2,
+            atype: AccountType::Product as u32,
+            ..Default::default()
+        };
+        let diagnosis = diagnose_price_account(bytemuck::bytes_of(&invalid_account));
+        assert!(diagnosis.length_ok);
+        assert_eq!(diagnosis.magic, Some(1));
+        assert_eq!(diagnosis.version, Some(2));
+        assert_eq!(diagnosis.account_type, Some(AccountType::Product as u32));
+        assert!(!diagnosis.is_valid());
+    }
+
+    #[test]
+    fn test_load_errors() {
+        let mut price_account = SolanaPriceAccount {
+            magic: MAGIC,
+            ver: VERSION_2,
+            atype: AccountType::Price as u32,
+            ..Default::default()
+        };
+        let size = std::mem::size_of::<SolanaPriceAccount>();
+
+        assert!(load_price_account::<32, ()>(bytemuck::bytes_of(&price_account)).is_ok());
+        assert_eq!(
+            load_price_account::<32, ()>(&bytemuck::bytes_of(&price_account)[..size - 1]),
+            Err(PythError::AccountTooSmall {
+                expected: size,
+                actual:   size - 1,
+            })
+        );
+        assert_eq!(
+            load_price_account::<32, ()>(&[]),
+            Err(PythError::AccountTooSmall {
+                expected: size,
+                actual:   0,
+            })
+        );
+
+        price_account.atype = AccountType::Product as u32;
+        assert_eq!(
+            load_price_account::<32, ()>(bytemuck::bytes_of(&price_account)),
+            Err(PythError::WrongAccountType)
+        );
+
+        price_account.ver = 1;
+        assert_eq!(
+            load_price_account::<32, ()>(bytemuck::bytes_of(&price_account)),
+            Err(PythError::BadVersionNumber)
+        );
+
+        price_account.magic = 0xdeadbeef;
+        assert_eq!(
+            load_price_account::<32, ()>(bytemuck::bytes_of(&price_account)),
+            Err(PythError::WrongMagic { found: 0xdeadbeef })
+        );
+
+        let mut product_account = product_account_with_attrs(&[]);
+        let data = bytemuck::bytes_of(&product_account);
+        assert!(load_product_account(data).is_ok());
+        assert_eq!(
+            load_product_account(&data[..100]),
+            Err(PythError::AccountTooSmall {
+                expected: PROD_ACCT_SIZE,
+                actual:   100,
+            })
+        );
+        assert_eq!(
+            load_mapping_account(data),
+            Err(PythError::AccountTooSmall {
+                expected: std::mem::size_of::<MappingAccount>(),
+                actual:   PROD_ACCT_SIZE,
+            })
+        );
+        product_account.magic = 0;
+        assert_eq!(
+            load_product_account(bytemuck::bytes_of(&product_account)),
+            Err(PythError::WrongMagic { found: 0 })
+        );
+    }
+
+    #[test]
+    fn test_price_account_json_round_trip() {
+        let publisher = PriceComp {
+            publisher: Pubkey::new_from_array([7; 32]),
+            agg:       PriceInfo {
+                price: 12340,
+                conf: 50,
+                status: PriceStatus::Trading,
+                pub_slot: 990,
+                ..Default::default()
+            },
+            latest:    PriceInfo {
+                price: 12350,
+                conf: 40,
+                status: PriceStatus::Trading,
+                pub_slot: 1000,
+                ..Default::default()
+            },
+        };
+        let price_account = SolanaPriceAccount::builder()
+            .expo(-2)
+            .trading(12345, 67, 1000)
+            .timestamp(200)
+            .prev_price(12000)
+            .prev_conf(60)
+            .prev_slot(990)
+            .prev_timestamp(190)
+            .ema_price(12100)
+            .ema_conf(70)
+            .component(publisher)
+            .component(publisher)
+            .build();
+        let price_account = SolanaPriceAccount {
+            prod: Pubkey::new_from_array([1; 32]),
+            next: Pubkey::new_from_array([2; 32]),
+            ..price_account
+        };
+
+        let json = serde_json::to_string(&price_account).unwrap();
+        let deserialized: SolanaPriceAccount = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, price_account);
+
+        // Pythnet accounts have more components than serde supports for arrays by default
+        let mut pythnet_account = PythnetPriceAccount {
+            magic: MAGIC,
+            ver: VERSION_2,
+            atype: AccountType::Price as u32,
+            extended: PriceAccountExt {
+                price_cumulative: PriceCumulative {
+                    price:          i128::MAX,
+                    conf:           u128::MAX,
+                    num_down_slots: 5,
+                    unused:         0,
+                },
+            },
+            ..Default::default()
+        };
+        pythnet_account.comp[127] = publisher;
+
+        let json = serde_json::to_string(&pythnet_account).unwrap();
+        let deserialized: PythnetPriceAccount = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, pythnet_account);
+
+        // The number of components must match the account type
+        let json = serde_json::to_string(&price_account).unwrap();
+        assert!(serde_json::from_str::<PythnetPriceAccount>(&json).is_err());
+    }
+}
+
+/// Tests of the byte-parsing path, which must not depend on Solana types. These also run with
+/// `cargo test --no-default-features`, where `Pubkey` is a plain `[u8; 32]`.
+#[cfg(test)]
+mod test_raw {
+    use pyth_sdk::Price;
+
+    use super::{
+        diagnose_price_account,
+        load_price_account,
+        load_price_account_any,
+        load_price_account_versioned,
+        AccountType,
+        ParsedPriceAccount,
+        PriceAccountHeader,
+        PriceCumulative,
+        PriceInfo,
+        PriceStatus,
+        PriceType,
+        PythnetPriceAccount,
+        SolanaPriceAccount,
+        MAGIC,
+        VERSION_2,
+    };
+    use crate::{
+        price_from_solana_bytes_checked,
+        PythError,
+    };
+
+    #[test]
+    fn test_price_no_older_than_slot() {
+        let mut price_account = SolanaPriceAccount {
+            expo: 5,
+            agg: PriceInfo {
+                price: 10,
+                conf: 20,
+                status: PriceStatus::Trading,
+                pub_slot: 30,
+                ..Default::default()
+            },
+            timestamp: 200,
+            prev_timestamp: 100,
+            prev_price: 60,
+            prev_conf: 70,
+            prev_slot: 10,
+            ..Default::default()
+        };
+        let current_price = Price {
+            price:        10,
+            conf:         20,
+            expo:         5,
+            publish_time: 200,
+        };
+        let prev_price = Price {
+            price:        60,
+            conf:         70,
+            expo:         5,
+            publish_time: 100,
+        };
+
+        assert_eq!(
+            price_account.get_price_no_older_than_slot(35, 5),
+            Some(current_price)
+        );
+        assert_eq!(price_account.get_price_no_older_than_slot(36, 5), None);
+
+        // Falls back to the previous price if the current price is not trading
+        price_account.agg.status = PriceStatus::Unknown;
+        assert_eq!(
+            price_account.get_price_no_older_than_slot(35, 25),
+            Some(prev_price)
+        );
+        assert_eq!(price_account.get_price_no_older_than_slot(35, 24), None);
+
+        // A threshold larger than the current slot doesn't underflow
+        assert_eq!(
+            price_account.get_price_no_older_than_slot(5, 100),
+            Some(prev_price)
+        );
+        price_account.agg.status = PriceStatus::Trading;
+        assert_eq!(
+            price_account.get_price_no_older_than_slot(5, 100),
+            Some(current_price)
+        );
+        assert_eq!(
+            price_account.get_price_no_older_than_slot(0, u64::MAX),
+            Some(current_price)
+        );
+    }
+
+    #[test]
+    fn test_header() {
+        let mut price_account = SolanaPriceAccount {
+            magic: MAGIC,
+            ver: VERSION_2,
+            atype: AccountType::Price as u32,
+            size: std::mem::size_of::<SolanaPriceAccount>() as u32,
+            ptype: PriceType::Price,
+            expo: -5,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            price_account.header(),
+            Ok(PriceAccountHeader {
+                magic: MAGIC,
+                ver:   VERSION_2,
+                atype: AccountType::Price as u32,
+                size:  std::mem::size_of::<SolanaPriceAccount>() as u32,
+                ptype: PriceType::Price,
+                expo:  -5,
+            })
+        );
+        assert!(price_account.is_price_type());
+
+        price_account.ptype = PriceType::Unknown;
+        assert_eq!(
+            price_account.header().map(|header| header.ptype),
+            Ok(PriceType::Unknown)
+        );
+        assert!(!price_account.is_price_type());
+
+        price_account.ptype = PriceType::Price;
+        price_account.atype = AccountType::Product as u32;
+        assert_eq!(price_account.header(), Err(PythError::WrongAccountType));
+        assert!(!price_account.is_price_type());
+
+        price_account.atype = AccountType::Price as u32;
+        price_account.ver = VERSION_2 + 1;
+        assert_eq!(price_account.header(), Err(PythError::BadVersionNumber));
+
+        price_account.ver = VERSION_2;
+        price_account.magic = 0;
+        assert_eq!(
+            price_account.header(),
+            Err(PythError::WrongMagic { found: 0 })
+        );
+    }
+
+    #[test]
+    fn test_load_price_account_versioned() {
+        let mut price_account = SolanaPriceAccount::builder().trading(10, 1, 1000).build();
+
+        // VERSION_2, as accepted by `load_price_account`
+        assert_eq!(
+            load_price_account_versioned::<32, ()>(price_account.as_bytes(), &[VERSION_2]),
+            Ok(&price_account)
+        );
+        assert_eq!(
+            load_price_account::<32, ()>(price_account.as_bytes()),
+            Ok(&price_account)
+        );
+
+        // Unknown version
+        price_account.ver = 3;
+        assert_eq!(
+            load_price_account_versioned::<32, ()>(price_account.as_bytes(), &[VERSION_2]),
+            Err(PythError::BadVersionNumber)
+        );
+        assert_eq!(
+            load_price_account::<32, ()>(price_account.as_bytes()),
+            Err(PythError::BadVersionNumber)
+        );
+        assert_eq!(
+            load_price_account_versioned::<32, ()>(price_account.as_bytes(), &[]),
+            Err(PythError::BadVersionNumber)
+        );
+
+        // Custom set of versions
+        assert_eq!(
+            load_price_account_versioned::<32, ()>(price_account.as_bytes(), &[VERSION_2, 3]),
+            Ok(&price_account)
+        );
+        price_account.ver = VERSION_2;
+        assert_eq!(
+            load_price_account_versioned::<32, ()>(price_account.as_bytes(), &[VERSION_2, 3]),
+            Ok(&price_account)
+        );
+        assert_eq!(
+            load_price_account_versioned::<32, ()>(price_account.as_bytes(), &[3]),
+            Err(PythError::BadVersionNumber)
+        );
+
+        // The other checks still apply
+        price_account.ver = 3;
+        price_account.magic = 0;
+        assert_eq!(
+            load_price_account_versioned::<32, ()>(price_account.as_bytes(), &[3]),
+            Err(PythError::WrongMagic { found: 0 })
+        );
+    }
+
+    #[test]
+    fn test_price_account_bytes_round_trip() {
+        let price_account = SolanaPriceAccount {
+            magic: MAGIC,
+            ver: VERSION_2,
+            atype: AccountType::Price as u32,
+            size: std::mem::size_of::<SolanaPriceAccount>() as u32,
+            expo: -5,
+            num: 2,
+            timestamp: 200,
+            prev_slot: 90,
+            prev_price: 60,
+            prev_conf: 70,
+            prev_timestamp: 100,
+            agg: PriceInfo {
+                price: 10,
+                conf: 20,
+                status: PriceStatus::Trading,
+                pub_slot: 100,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let bytes = price_account.as_bytes();
+        assert_eq!(bytes.len(), std::mem::size_of::<SolanaPriceAccount>());
+        assert_eq!(load_price_account::<32, ()>(bytes), Ok(&price_account));
+        assert_eq!(SolanaPriceAccount::from_bytes(bytes), Ok(&price_account));
+
+        // Round trip through an owned buffer, as an account would be stored
+        let data = bytes.to_vec();
+        assert_eq!(SolanaPriceAccount::from_bytes(&data), Ok(&price_account));
+
+        // Pythnet accounts round trip as well
+        let pythnet_account = PythnetPriceAccount {
+            magic: MAGIC,
+            ver: VERSION_2,
+            atype: AccountType::Price as u32,
+            expo: -5,
+            ..Default::default()
+        };
+        assert_eq!(
+            PythnetPriceAccount::from_bytes(pythnet_account.as_bytes()),
+            Ok(&pythnet_account)
+        );
+
+        // Invalid accounts are rejected like `load_price_account`
+        let invalid_account = SolanaPriceAccount::default();
+        assert_eq!(
+            SolanaPriceAccount::from_bytes(invalid_account.as_bytes()),
+            load_price_account::<32, ()>(invalid_account.as_bytes())
+        );
+        assert!(SolanaPriceAccount::from_bytes(invalid_account.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_parse_price_account_from_bytes() {
+        let price_account = SolanaPriceAccount {
+            magic: MAGIC,
+            ver: VERSION_2,
+            atype: AccountType::Price as u32,
+            size: std::mem::size_of::<SolanaPriceAccount>() as u32,
+            expo: -2,
+            agg: PriceInfo {
+                price: 12345,
+                conf: 67,
+                status: PriceStatus::Trading,
+                ..Default::default()
+            },
+            timestamp: 200,
+            ..Default::default()
+        };
+        let data = bytemuck::bytes_of(&price_account);
+
+        assert!(diagnose_price_account(data).is_valid());
+        assert_eq!(load_price_account::<32, ()>(data), Ok(&price_account));
+        assert_eq!(
+            load_price_account_any(data),
+            Ok(ParsedPriceAccount::Solana(&price_account))
+        );
+        assert_eq!(
+            price_from_solana_bytes_checked(data, 210, 10),
+            Ok(Price {
+                price:        12345,
+                conf:         67,
+                expo:         -2,
+                publish_time: 200,
+            })
+        );
+    }
+
+    #[test]
+    fn test_downtime_ratio() {
+        let start = PriceCumulative {
+            num_down_slots: 10,
+            ..Default::default()
+        };
+        let end = PriceCumulative {
+            num_down_slots: 35,
+            ..Default::default()
+        };
+
+        // (35 - 10) / (1100 - 1000)
+        assert_eq!(
+            PriceCumulative::downtime_ratio(&start, 1000, &end, 1100),
+            Some(0.25)
+        );
+        assert_eq!(
+            PriceCumulative::downtime_ratio(&start, 1000, &start, 1100),
+            Some(0.0)
+        );
+        assert_eq!(
+            PriceCumulative::downtime_ratio(&start, 1000, &end, 1025),
+            Some(1.0)
+        );
+
+        // Zero or inverted slot gaps
+        assert_eq!(
+            PriceCumulative::downtime_ratio(&start, 1000, &end, 1000),
+            None
+        );
+        assert_eq!(
+            PriceCumulative::downtime_ratio(&start, 1100, &end, 1000),
+            None
+        );
+
+        // Swapped snapshots
+        assert_eq!(
+            PriceCumulative::downtime_ratio(&end, 1000, &start, 1100),
+            None
+        );
+    }
 }
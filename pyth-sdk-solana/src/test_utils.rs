@@ -0,0 +1,234 @@
+//! Helpers for building raw Pyth account data in tests, enabled by the `test-utils` feature.
+
+use pyth_sdk::UnixTimestamp;
+
+use crate::state::{
+    AccountType,
+    PriceComp,
+    PriceInfo,
+    PriceStatus,
+    PriceType,
+    Rational,
+    SolanaPriceAccount,
+    MAGIC,
+    VERSION_2,
+};
+
+/// Builder for `SolanaPriceAccount`s in tests, see `SolanaPriceAccount::builder`.
+///
+/// The header of the account (`magic`, `ver`, `atype`, `size` and `ptype`) is valid by default,
+/// so the built account is accepted by `load_price_account`. All other fields default to zero.
+#[derive(Copy, Clone, Debug)]
+pub struct SolanaPriceAccountBuilder {
+    price_account: SolanaPriceAccount,
+}
+
+impl Default for SolanaPriceAccountBuilder {
+    fn default() -> Self {
+        SolanaPriceAccountBuilder {
+            price_account: SolanaPriceAccount {
+                magic: MAGIC,
+                ver: VERSION_2,
+                atype: AccountType::Price as u32,
+                size: std::mem::size_of::<SolanaPriceAccount>() as u32,
+                ptype: PriceType::Price,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl SolanaPriceAccountBuilder {
+    pub fn expo(mut self, expo: i32) -> Self {
+        self.price_account.expo = expo;
+        self
+    }
+
+    pub fn agg(mut self, agg: PriceInfo) -> Self {
+        self.price_account.agg = agg;
+        self
+    }
+
+    /// Set the aggregate price to a `Trading` price of `price +- conf` published in `pub_slot`.
+    pub fn trading(self, price: i64, conf: u64, pub_slot: u64) -> Self {
+        self.agg(PriceInfo {
+            price,
+            conf,
+            status: PriceStatus::Trading,
+            pub_slot,
+            ..Default::default()
+        })
+    }
+
+    pub fn timestamp(mut self, timestamp: UnixTimestamp) -> Self {
+        self.price_account.timestamp = timestamp;
+        self
+    }
+
+    pub fn prev_price(mut self, prev_price: i64) -> Self {
+        self.price_account.prev_price = prev_price;
+        self
+    }
+
+    pub fn prev_conf(mut self, prev_conf: u64) -> Self {
+        self.price_account.prev_conf = prev_conf;
+        self
+    }
+
+    pub fn prev_slot(mut self, prev_slot: u64) -> Self {
+        self.price_account.prev_slot = prev_slot;
+        self
+    }
+
+    pub fn prev_timestamp(mut self, prev_timestamp: UnixTimestamp) -> Self {
+        self.price_account.prev_timestamp = prev_timestamp;
+        self
+    }
+
+    pub fn ema_price(mut self, ema_price: i64) -> Self {
+        self.price_account.ema_price = Rational {
+            val: ema_price,
+            ..Default::default()
+        };
+        self
+    }
+
+    pub fn ema_conf(mut self, ema_conf: u64) -> Self {
+        self.price_account.ema_conf = Rational {
+            val: ema_conf as i64,
+            ..Default::default()
+        };
+        self
+    }
+
+    /// Add a publisher component after the existing ones and increment `num`.
+    ///
+    /// Panics if the account already has the maximum number of components.
+    pub fn component(mut self, component: PriceComp) -> Self {
+        let num = self.price_account.num as usize;
+        self.price_account.comp[num] = component;
+        self.price_account.num += 1;
+        self
+    }
+
+    pub fn build(self) -> SolanaPriceAccount {
+        self.price_account
+    }
+}
+
+impl SolanaPriceAccount {
+    /// Start building a `SolanaPriceAccount` with a valid header, e.g., for tests.
+    pub fn builder() -> SolanaPriceAccountBuilder {
+        SolanaPriceAccountBuilder::default()
+    }
+
+    /// Build the raw bytes of a valid Solana price account with a trading aggregate price of
+    /// `price +- conf * 10^expo` published at `publish_time`.
+    ///
+    /// The previous price and the EMA price are set to the same values, so the account yields the
+    /// same price regardless of which one is read. The result is accepted by `load_price_account`
+    /// and `SolanaPriceAccount::from_bytes`.
+    pub fn mock_bytes(price: i64, conf: u64, expo: i32, publish_time: UnixTimestamp) -> Vec<u8> {
+        SolanaPriceAccount::builder()
+            .expo(expo)
+            .trading(price, conf, 0)
+            .timestamp(publish_time)
+            .prev_price(price)
+            .prev_conf(conf)
+            .prev_timestamp(publish_time)
+            .ema_price(price)
+            .ema_conf(conf)
+            .build()
+            .as_bytes()
+            .to_vec()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pyth_sdk::Price;
+
+    use crate::state::{
+        load_price_account,
+        PriceStatus,
+        SolanaPriceAccount,
+    };
+
+    #[test]
+    fn test_mock_bytes() {
+        let data = SolanaPriceAccount::mock_bytes(12345, 67, -2, 1000);
+        let price_account = load_price_account::<32, ()>(&data).unwrap();
+
+        assert_eq!(price_account.agg.price, 12345);
+        assert_eq!(price_account.agg.conf, 67);
+        assert_eq!(price_account.agg.status, PriceStatus::Trading);
+        assert_eq!(price_account.expo, -2);
+        assert_eq!(price_account.timestamp, 1000);
+        assert_eq!(
+            price_account.get_price_newer_than_timestamp(1000),
+            Some(Price {
+                price:        12345,
+                conf:         67,
+                expo:         -2,
+                publish_time: 1000,
+            })
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "solana-program")]
+    fn test_builder() {
+        use crate::state::{
+            PriceComp,
+            PriceInfo,
+        };
+
+        let publisher = PriceComp {
+            agg: PriceInfo {
+                price: 12340,
+                conf: 50,
+                status: PriceStatus::Trading,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let price_account = SolanaPriceAccount::builder()
+            .expo(-2)
+            .trading(12345, 67, 1000)
+            .timestamp(200)
+            .prev_price(12000)
+            .prev_conf(60)
+            .prev_slot(990)
+            .prev_timestamp(190)
+            .ema_price(12100)
+            .ema_conf(70)
+            .component(publisher)
+            .component(publisher)
+            .build();
+
+        assert!(load_price_account::<32, ()>(price_account.as_bytes()).is_ok());
+        assert_eq!(price_account.num_publishers(), 2);
+        assert_eq!(price_account.aggregate_info().pub_slot, 1000);
+        assert_eq!(price_account.prev_slot, 990);
+
+        let price_feed = price_account.to_price_feed(&Default::default());
+        assert_eq!(
+            price_feed.get_price_unchecked(),
+            Price {
+                price:        12345,
+                conf:         67,
+                expo:         -2,
+                publish_time: 200,
+            }
+        );
+        assert_eq!(
+            price_feed.get_ema_price_unchecked(),
+            Price {
+                price:        12100,
+                conf:         70,
+                expo:         -2,
+                publish_time: 200,
+            }
+        );
+    }
+}
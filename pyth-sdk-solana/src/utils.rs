@@ -0,0 +1,36 @@
+/// This module helps serde to serialize deserialize arrays of any length, such as the publisher
+/// components of a price account.
+///
+/// Serde only implements `Serialize` and `Deserialize` for arrays of up to 32 elements, which
+/// excludes the 128 components of `PythnetPriceAccount` and arrays of a const generic length.
+pub mod as_array {
+    use serde::de::Error;
+    use serde::{
+        Deserialize,
+        Deserializer,
+        Serialize,
+        Serializer,
+    };
+    use std::convert::TryInto;
+
+    pub fn serialize<T, S, const N: usize>(value: &[T; N], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        S: Serializer,
+    {
+        serializer.collect_seq(value)
+    }
+
+    pub fn deserialize<'de, T, D, const N: usize>(deserializer: D) -> Result<[T; N], D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        let values = Vec::<T>::deserialize(deserializer)?;
+        let len = values.len();
+
+        values.try_into().map_err(|_| {
+            D::Error::invalid_length(len, &format!("an array of length {}", N).as_str())
+        })
+    }
+}
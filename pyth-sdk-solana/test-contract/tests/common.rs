@@ -5,19 +5,58 @@ use solana_sdk::transaction::Transaction;
 use test_contract::id;
 use test_contract::processor::process_instruction;
 
-// Panics if running instruction fails
-pub async fn test_instr_exec_ok(instr: Instruction) {
-    let mut context = ProgramTest::new("test_contract", id(), processor!(process_instruction))
-        .start_with_context()
-        .await;
+async fn start_context(compute_max_units: Option<u64>) -> ProgramTestContext {
+    let mut program_test = ProgramTest::new("test_contract", id(), processor!(process_instruction));
+    // Compute units are only representative when the program runs as BPF, which `cargo test-bpf`
+    // and `cargo test-sbf` signal with the `test-bpf` feature.
+    if let Some(compute_max_units) = compute_max_units.filter(|_| cfg!(feature = "test-bpf")) {
+        program_test.set_compute_max_units(compute_max_units);
+    }
+
+    let mut context = program_test.start_with_context().await;
 
     context.warp_to_slot(1000).unwrap();
 
+    context
+}
+
+fn signed_transaction(context: &ProgramTestContext, instr: Instruction) -> Transaction {
     let mut transaction = Transaction::new_with_payer(&[instr], Some(&context.payer.pubkey()));
     transaction.sign(&[&context.payer], context.last_blockhash);
+    transaction
+}
+
+// Panics if running instruction fails
+pub async fn test_instr_exec_ok(instr: Instruction) {
+    test_instr_exec_ok_with_max_units_opt(instr, None).await
+}
+
+// Panics if running instruction fails or, when running as BPF, consumes more than `max_units`
+// compute units
+pub async fn test_instr_exec_ok_with_max_units(instr: Instruction, max_units: u64) {
+    test_instr_exec_ok_with_max_units_opt(instr, Some(max_units)).await
+}
+
+async fn test_instr_exec_ok_with_max_units_opt(instr: Instruction, max_units: Option<u64>) {
+    let mut context = start_context(max_units).await;
+    let transaction = signed_transaction(&context, instr);
     context
         .banks_client
         .process_transaction(transaction)
         .await
         .unwrap()
 }
+
+// Get the compute units consumed by running instruction. Panics if running instruction fails.
+pub async fn instr_compute_units(instr: Instruction) -> u64 {
+    let mut context = start_context(None).await;
+    let transaction = signed_transaction(&context, instr);
+    let simulation = context
+        .banks_client
+        .simulate_transaction(transaction)
+        .await
+        .unwrap();
+
+    simulation.result.unwrap().unwrap();
+    simulation.simulation_details.unwrap().units_consumed
+}
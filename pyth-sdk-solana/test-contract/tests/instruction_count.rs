@@ -4,7 +4,11 @@ use test_contract::instruction;
 use pyth_sdk_solana::Price;
 
 mod common;
-use common::test_instr_exec_ok;
+use common::{
+    instr_compute_units,
+    test_instr_exec_ok,
+    test_instr_exec_ok_with_max_units,
+};
 
 fn pc(price: i64, conf: u64, expo: i32) -> Price {
     Price {
@@ -25,6 +29,22 @@ async fn test_scale_to_exponent_down() {
     test_instr_exec_ok(instruction::scale_to_exponent(pc(1, u64::MAX, -1000), 1000)).await
 }
 
+// Scaling down by 18 digits divides once by 10^18 rather than once per digit, so this costs about
+// as much as scaling down by a single digit.
+#[tokio::test]
+async fn test_scale_to_exponent_down_single_step() {
+    let single_digit_units = instr_compute_units(instruction::scale_to_exponent(
+        pc(i64::MAX, u64::MAX, -1),
+        0,
+    ))
+    .await;
+    test_instr_exec_ok_with_max_units(
+        instruction::scale_to_exponent(pc(i64::MAX, u64::MAX, -18), 0),
+        single_digit_units + 200,
+    )
+    .await
+}
+
 #[tokio::test]
 async fn test_scale_to_exponent_up() {
     test_instr_exec_ok(instruction::scale_to_exponent(pc(1, u64::MAX, 1000), -1000)).await
@@ -50,6 +70,20 @@ async fn test_normalize_min_price() {
     test_instr_exec_ok(instruction::normalize(pc(i64::MIN, 1, 0))).await
 }
 
+// Normalizing the largest price and confidence drops 11 digits. The number of digits is computed
+// directly rather than dividing by 10 once per digit, so this costs about as much as dropping a
+// single digit.
+#[tokio::test]
+async fn test_normalize_max_price_and_conf() {
+    let single_digit_units =
+        instr_compute_units(instruction::normalize(pc(2_684_354_550, 2_684_354_550, 0))).await;
+    test_instr_exec_ok_with_max_units(
+        instruction::normalize(pc(i64::MAX, u64::MAX, 0)),
+        single_digit_units + 100,
+    )
+    .await
+}
+
 #[tokio::test]
 async fn test_normalize_best_case() {
     test_instr_exec_ok(instruction::normalize(pc(1, 1, 0))).await
@@ -65,6 +99,25 @@ async fn test_div_max_price_2() {
     test_instr_exec_ok(instruction::divide(pc(i64::MAX, 1, 0), pc(i64::MAX, 1, 0))).await;
 }
 
+// With typical confidence intervals, the confidence of the quotient is computed in u64 rather than
+// u128 arithmetic, which saves about 3k compute units compared to a quotient that needs u128.
+#[tokio::test]
+async fn test_div_typical_conf() {
+    let u128_units = instr_compute_units(instruction::divide(
+        pc(2_000_000_000, 1_000_000, -8),
+        pc(1, 10, -8),
+    ))
+    .await;
+    test_instr_exec_ok_with_max_units(
+        instruction::divide(
+            pc(2_000_000_000, 1_000_000, -8),
+            pc(150_000_000, 50_000, -8),
+        ),
+        u128_units.saturating_sub(500),
+    )
+    .await;
+}
+
 #[tokio::test]
 async fn test_mul_max_price() {
     test_instr_exec_ok(instruction::multiply(pc(i64::MAX, 1, 2), pc(123, 1, 2))).await;
@@ -0,0 +1,82 @@
+use std::fmt;
+
+/// Errors that may be returned when reading prices from a `PriceFeed` or when performing
+/// arithmetic on `Price`s, e.g. `Price::try_div`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OracleError {
+    /// No price satisfying the requested constraints (e.g. freshness) is available.
+    NoneEncountered,
+    /// An intermediate value or the result doesn't fit in its integer type.
+    Overflow,
+    /// The divisor price is zero.
+    DivByZero,
+    /// The confidence interval of the result is too wide to be represented, which happens if an
+    /// argument's confidence interval is much larger than its price.
+    ConfidenceTooWide,
+    /// An unsigned argument is larger than `i64::MAX`.
+    I64ConversionError,
+    /// The arguments have different exponents, e.g. in `Price::try_add`.
+    ExponentMismatch,
+    /// The collateral valuation discount at 0 deposits is larger than at the deposits endpoint,
+    /// i.e., `rate_discount_initial < rate_discount_final`.
+    InitialDiscountExceedsFinalDiscount,
+    /// The borrow valuation premium at 0 borrows is larger than at the borrows endpoint, i.e.,
+    /// `rate_premium_initial > rate_premium_final`.
+    InitialPremiumExceedsFinalPremium,
+    /// The deposits or borrows endpoint of a valuation is zero.
+    ZeroEndpoint,
+}
+
+impl fmt::Display for OracleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OracleError::NoneEncountered => write!(f, "No price available"),
+            OracleError::Overflow => write!(f, "Arithmetic overflow"),
+            OracleError::DivByZero => write!(f, "Division by a zero price"),
+            OracleError::ConfidenceTooWide => write!(f, "Confidence interval is too wide"),
+            OracleError::I64ConversionError => write!(f, "Value does not fit in an i64"),
+            OracleError::ExponentMismatch => write!(f, "Prices have different exponents"),
+            OracleError::InitialDiscountExceedsFinalDiscount => {
+                write!(f, "Initial discount exceeds final discount")
+            }
+            OracleError::InitialPremiumExceedsFinalPremium => {
+                write!(f, "Initial premium exceeds final premium")
+            }
+            OracleError::ZeroEndpoint => write!(f, "Valuation endpoint is zero"),
+        }
+    }
+}
+
+impl std::error::Error for OracleError {
+}
+
+/// Errors that may be returned when parsing an `Identifier` from base58, see
+/// `Identifier::from_base58`.
+#[cfg(feature = "base58")]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Base58Error {
+    /// The string is not valid base58.
+    Decode(bs58::decode::Error),
+    /// The string decodes to this many bytes rather than 32.
+    InvalidLength(usize),
+}
+
+#[cfg(feature = "base58")]
+impl fmt::Display for Base58Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Base58Error::Decode(e) => write!(f, "Invalid base58 string: {}", e),
+            Base58Error::InvalidLength(len) => {
+                write!(
+                    f,
+                    "Invalid identifier length: expected 32 bytes, got {}",
+                    len
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "base58")]
+impl std::error::Error for Base58Error {
+}
@@ -5,12 +5,28 @@ use borsh::{
 
 use hex::FromHexError;
 use schemars::JsonSchema;
+#[cfg(feature = "content-hash")]
+use sha2::{
+    Digest,
+    Sha256,
+};
+use std::convert::TryFrom;
 use std::fmt;
+use std::str::FromStr;
 
 pub mod utils;
 
+mod error;
+#[cfg(feature = "base58")]
+pub use error::Base58Error;
+pub use error::OracleError;
+
 mod price;
-pub use price::Price;
+pub use price::{
+    NormalizedPrice,
+    Price,
+    PriceNumeric,
+};
 
 #[derive(
     Copy,
@@ -47,11 +63,52 @@ impl Identifier {
         hex::encode(self.0)
     }
 
+    /// Abbreviated hex representation, e.g. `0x63f3…c8b3`, keeping the first and last 4 hex
+    /// digits. Useful for compact logging; use `to_hex` when the full identifier is needed.
+    pub fn to_hex_short(&self) -> String {
+        let hex = self.to_hex();
+        format!("0x{}…{}", &hex[..4], &hex[hex.len() - 4..])
+    }
+
     pub fn from_hex<T: AsRef<[u8]>>(s: T) -> Result<Identifier, FromHexError> {
         let mut bytes = [0u8; 32];
         hex::decode_to_slice(s, &mut bytes)?;
         Ok(Identifier::new(bytes))
     }
+
+    /// Base58 representation, e.g. for identifiers of Solana price accounts, which are usually
+    /// written as base58 public keys.
+    #[cfg(feature = "base58")]
+    pub fn to_base58(&self) -> String {
+        bs58::encode(self.0).into_string()
+    }
+
+    /// Parses a base58-encoded identifier, e.g. a Solana public key.
+    #[cfg(feature = "base58")]
+    pub fn from_base58(s: &str) -> Result<Identifier, Base58Error> {
+        let bytes = bs58::decode(s).into_vec().map_err(Base58Error::Decode)?;
+        Identifier::try_from(bytes.as_slice()).map_err(|_| Base58Error::InvalidLength(bytes.len()))
+    }
+}
+
+/// Parses a hex-encoded identifier, with or without a `0x` prefix.
+impl FromStr for Identifier {
+    type Err = FromHexError;
+
+    fn from_str(s: &str) -> Result<Identifier, FromHexError> {
+        Identifier::from_hex(s.strip_prefix("0x").unwrap_or(s))
+    }
+}
+
+/// Converts a 32-byte slice into an identifier, returning `FromHexError::InvalidStringLength` if
+/// the slice has any other length.
+impl TryFrom<&[u8]> for Identifier {
+    type Error = FromHexError;
+
+    fn try_from(bytes: &[u8]) -> Result<Identifier, FromHexError> {
+        let bytes = <[u8; 32]>::try_from(bytes).map_err(|_| FromHexError::InvalidStringLength)?;
+        Ok(Identifier::new(bytes))
+    }
 }
 
 impl fmt::Debug for Identifier {
@@ -86,6 +143,9 @@ pub type ProductIdentifier = Identifier;
 pub type UnixTimestamp = i64;
 pub type DurationInSeconds = u64;
 
+/// Default maximum age of a price before it is considered to be stale.
+pub const DEFAULT_VALID_PERIOD_SECS: DurationInSeconds = 60;
+
 /// Represents a current aggregation price from pyth publisher feeds.
 #[derive(
     Copy,
@@ -112,6 +172,9 @@ pub struct PriceFeed {
 
 impl PriceFeed {
     /// Constructs a new Price Feed
+    ///
+    /// The publish times of `price` and `ema_price` are kept as given, so they may differ, e.g.,
+    /// when building fixtures where the EMA was updated at a different time than the spot price.
     #[allow(clippy::too_many_arguments)]
     pub fn new(id: PriceIdentifier, price: Price, ema_price: Price) -> PriceFeed {
         PriceFeed {
@@ -121,6 +184,26 @@ impl PriceFeed {
         }
     }
 
+    /// Get the unique identifier of this price feed.
+    pub fn id(&self) -> PriceIdentifier {
+        self.id
+    }
+
+    /// Get the publish time of the current price.
+    pub fn publish_time(&self) -> UnixTimestamp {
+        self.price.publish_time
+    }
+
+    /// Check whether the current price was published strictly after `stored_publish_time`, e.g.,
+    /// to reject replays of updates that are not newer than a cached price.
+    pub fn is_newer_than(&self, stored_publish_time: UnixTimestamp) -> bool {
+        self.price.publish_time > stored_publish_time
+    }
+
+    /// Get the publish time of the exponentially-weighted moving average (EMA) price.
+    pub fn ema_publish_time(&self) -> UnixTimestamp {
+        self.ema_price.publish_time
+    }
 
     /// Get the "unchecked" price and confidence interval as fixed-point numbers of the form
     /// a * 10^e along with its publish time.
@@ -176,6 +259,138 @@ impl PriceFeed {
         Some(price)
     }
 
+    /// Same as `get_price_no_older_than`, but also returns the id of this feed, e.g., for
+    /// collecting the prices of several feeds into a map keyed by id.
+    pub fn price_with_id_no_older_than(
+        &self,
+        current_time: UnixTimestamp,
+        age: DurationInSeconds,
+    ) -> Option<(PriceIdentifier, Price)> {
+        Some((self.id, self.get_price_no_older_than(current_time, age)?))
+    }
+
+    /// Same as `get_price_no_older_than`, but returns `OracleError::NoneEncountered` instead of
+    /// `None` if the price wasn't updated sufficiently recently, so that it can be used with `?`.
+    pub fn require_price_no_older_than(
+        &self,
+        current_time: UnixTimestamp,
+        age: DurationInSeconds,
+    ) -> Result<Price, OracleError> {
+        self.get_price_no_older_than(current_time, age)
+            .ok_or(OracleError::NoneEncountered)
+    }
+
+    /// Get the price as long as it was updated within `DEFAULT_VALID_PERIOD_SECS` seconds of the
+    /// `current_time`.
+    ///
+    /// This is equivalent to `get_price_no_older_than(current_time, DEFAULT_VALID_PERIOD_SECS)`.
+    pub fn get_price_no_older_than_default(&self, current_time: UnixTimestamp) -> Option<Price> {
+        self.get_price_no_older_than(current_time, DEFAULT_VALID_PERIOD_SECS)
+    }
+
+    /// Get the price as long as it was updated within `age` seconds of the `current_time`, treating
+    /// a zero publish time as "never published".
+    ///
+    /// This function behaves like `get_price_no_older_than`, except that it always returns `None`
+    /// if the publish time of the price is 0 (e.g. a default-constructed `Price`), regardless of
+    /// `current_time` and `age`.
+    pub fn get_price_no_older_than_strict(
+        &self,
+        current_time: UnixTimestamp,
+        age: DurationInSeconds,
+    ) -> Option<Price> {
+        if self.price.publish_time == 0 {
+            return None;
+        }
+
+        self.get_price_no_older_than(current_time, age)
+    }
+
+    /// Get the price as long as it was published at most `max_age` seconds before and at most
+    /// `max_ahead` seconds after the `current_time`.
+    ///
+    /// `get_price_no_older_than` accepts publish times on either side of `current_time`, so a
+    /// price dated far in the future (e.g. due to clock skew) is considered fresh. This function
+    /// bounds how far ahead the publish time may be separately, e.g., `max_ahead` of 0 rejects any
+    /// price from the future.
+    pub fn get_price_no_older_than_bounded(
+        &self,
+        current_time: UnixTimestamp,
+        max_age: DurationInSeconds,
+        max_ahead: DurationInSeconds,
+    ) -> Option<Price> {
+        let price = self.get_price_unchecked();
+
+        let time_ahead = i128::from(price.publish_time) - i128::from(current_time);
+
+        if time_ahead > i128::from(max_ahead) || -time_ahead > i128::from(max_age) {
+            return None;
+        }
+
+        Some(price)
+    }
+
+    /// Get the price as long as it was updated within `age` seconds of the `current_time` and its
+    /// confidence ratio (see `Price::confidence_ratio`) is at most `max_ratio`.
+    ///
+    /// Following the best practices, this rejects prices whose confidence interval is too wide
+    /// relative to the price. Returns `None` if the price wasn't updated sufficiently recently
+    /// (see `get_price_no_older_than`), if the price is 0, or if the ratio exceeds `max_ratio`.
+    pub fn get_price_no_older_than_with_max_conf_ratio(
+        &self,
+        current_time: UnixTimestamp,
+        age: DurationInSeconds,
+        max_ratio: f64,
+    ) -> Option<Price> {
+        let price = self.get_price_no_older_than(current_time, age)?;
+
+        if price.confidence_ratio()? > max_ratio {
+            return None;
+        }
+
+        Some(price)
+    }
+
+    /// Get the lower end of the confidence interval of the price, i.e., `price - conf`, as long as
+    /// it was updated within `age` seconds of the `current_time`.
+    ///
+    /// Following the best practices, use this bound to value collateral. The returned `Price` has
+    /// its `price` already adjusted and a `conf` of 0. Returns `None` if the price wasn't updated
+    /// sufficiently recently (see `get_price_no_older_than`) or if the bound overflows.
+    pub fn get_price_no_older_than_with_conf_lower(
+        &self,
+        current_time: UnixTimestamp,
+        age: DurationInSeconds,
+    ) -> Option<Price> {
+        let price = self.get_price_no_older_than(current_time, age)?;
+
+        Some(Price {
+            price: price.price.checked_sub(i64::try_from(price.conf).ok()?)?,
+            conf: 0,
+            ..price
+        })
+    }
+
+    /// Get the upper end of the confidence interval of the price, i.e., `price + conf`, as long as
+    /// it was updated within `age` seconds of the `current_time`.
+    ///
+    /// Following the best practices, use this bound to value debt. The returned `Price` has its
+    /// `price` already adjusted and a `conf` of 0. Returns `None` if the price wasn't updated
+    /// sufficiently recently (see `get_price_no_older_than`) or if the bound overflows.
+    pub fn get_price_no_older_than_with_conf_upper(
+        &self,
+        current_time: UnixTimestamp,
+        age: DurationInSeconds,
+    ) -> Option<Price> {
+        let price = self.get_price_no_older_than(current_time, age)?;
+
+        Some(Price {
+            price: price.price.checked_add(i64::try_from(price.conf).ok()?)?,
+            conf: 0,
+            ..price
+        })
+    }
+
     /// Get the exponentially-weighted moving average (EMA) price as long as it was updated within
     /// `age` seconds of the `current_time`.
     ///
@@ -201,7 +416,223 @@ impl PriceFeed {
 
         Some(price)
     }
+
+    /// Get both the price and the EMA price as long as both were updated within `age` seconds of
+    /// the `current_time`.
+    ///
+    /// Returns `(price, ema_price)`, or `None` if either of them wasn't updated sufficiently
+    /// recently (see `get_price_no_older_than` and `get_ema_price_no_older_than`), so that both
+    /// are always checked consistently.
+    pub fn get_prices_no_older_than(
+        &self,
+        current_time: UnixTimestamp,
+        age: DurationInSeconds,
+    ) -> Option<(Price, Price)> {
+        Some((
+            self.get_price_no_older_than(current_time, age)?,
+            self.get_ema_price_no_older_than(current_time, age)?,
+        ))
+    }
+
+    /// Check whether the confidence interval of the EMA price looks plausible, i.e., it is nonzero
+    /// and at most `max_conf_bps` basis points of the magnitude of the EMA price.
+    ///
+    /// The EMA confidence is computed in a somewhat questionable way (see
+    /// `get_ema_price_unchecked`); a zero or very wide confidence is a sign that it shouldn't be
+    /// relied upon.
+    pub fn ema_conf_is_reliable(&self, max_conf_bps: u64) -> bool {
+        let ema_price = self.ema_price;
+        ema_price.conf != 0
+            && (ema_price.conf as u128) * 10_000
+                <= (ema_price.price.unsigned_abs() as u128) * (max_conf_bps as u128)
+    }
+
+    /// Get the EMA price as long as it was updated within `age` seconds of the `current_time` and
+    /// its confidence interval is reliable according to `ema_conf_is_reliable(max_conf_bps)`.
+    ///
+    /// Returns `None` if the EMA price wasn't updated sufficiently recently (see
+    /// `get_ema_price_no_older_than`), or if its confidence is 0 or wider than `max_conf_bps` basis
+    /// points of the price.
+    pub fn get_ema_price_no_older_than_checked(
+        &self,
+        current_time: UnixTimestamp,
+        age: DurationInSeconds,
+        max_conf_bps: u64,
+    ) -> Option<Price> {
+        if !self.ema_conf_is_reliable(max_conf_bps) {
+            return None;
+        }
+
+        self.get_ema_price_no_older_than(current_time, age)
+    }
+
+    /// Get the value of `amount * 10^amount_expo` units of collateral, discounted by a collateral
+    /// factor of `ltv_bps` basis points, with exponent `result_expo`.
+    ///
+    /// Following the best practices for valuing collateral, the value is computed from the lower
+    /// end of the confidence interval, i.e., `(price - conf) * amount * ltv_bps / 10000`, and the
+    /// returned `Price` has a confidence of 0. Returns `None` if the price was not updated within
+    /// `age` seconds of `current_time` (see `get_price_no_older_than`), or if the result cannot be
+    /// represented with the requested exponent.
+    pub fn collateral_value(
+        &self,
+        amount: i64,
+        amount_expo: i32,
+        ltv_bps: u64,
+        current_time: UnixTimestamp,
+        age: DurationInSeconds,
+        result_expo: i32,
+    ) -> Option<Price> {
+        self.get_price_no_older_than_with_conf_lower(current_time, age)?
+            .cmul(amount, amount_expo)?
+            .cmul(i64::try_from(ltv_bps).ok()?, -4)?
+            .scale_to_exponent(result_expo)
+    }
+
+    /// Get a conservative collateral price with exponent `result_expo`: the smaller of the lower
+    /// ends of the confidence intervals of the price and of the EMA price, i.e.,
+    /// `min(price - conf, ema_price - ema_conf)`.
+    ///
+    /// Bounding the price by the EMA protects against short-lived price spikes. Returns `None`
+    /// unless both prices were updated within `age` seconds of `current_time`, or if either value
+    /// cannot be represented with the requested exponent. The returned `Price` has a confidence of
+    /// 0 and the publish time of the binding price.
+    pub fn conservative_collateral_price(
+        &self,
+        current_time: UnixTimestamp,
+        age: DurationInSeconds,
+        result_expo: i32,
+    ) -> Option<Price> {
+        let price = self.get_price_no_older_than(current_time, age)?;
+        let ema_price = self.get_ema_price_no_older_than(current_time, age)?;
+
+        let lower_bound = |price: Price| {
+            Price {
+                price: price.price.checked_sub(i64::try_from(price.conf).ok()?)?,
+                conf: 0,
+                ..price
+            }
+            .scale_to_exponent(result_expo)
+        };
+        let price = lower_bound(price)?;
+        let ema_price = lower_bound(ema_price)?;
+
+        if ema_price.price < price.price {
+            Some(ema_price)
+        } else {
+            Some(price)
+        }
+    }
+
+    /// Get a copy of this price feed with both the price and the EMA price rescaled to
+    /// `target_expo`. See `Price::scale_to_exponent`.
+    ///
+    /// The id and the publish times are unchanged. Returns `None` if either price cannot be
+    /// represented with the requested exponent.
+    pub fn scale_to_exponent(&self, target_expo: i32) -> Option<PriceFeed> {
+        Some(PriceFeed::new(
+            self.id,
+            self.price.scale_to_exponent(target_expo)?,
+            self.ema_price.scale_to_exponent(target_expo)?,
+        ))
+    }
+
+    /// Get the price feed of this feed's asset in units of `quote`'s asset, e.g., an mSOL/SOL
+    /// feed from the mSOL/USD and SOL/USD feeds. The result has the id `new_id`.
+    ///
+    /// Both the price and the EMA price of this feed are divided by those of `quote` as in
+    /// `Price::get_price_in_quote`. Returns `None` if either quotient cannot be represented with
+    /// `result_expo`.
+    pub fn get_feed_in_quote(
+        &self,
+        quote: &PriceFeed,
+        result_expo: i32,
+        new_id: PriceIdentifier,
+    ) -> Option<PriceFeed> {
+        Some(PriceFeed::new(
+            new_id,
+            self.price.get_price_in_quote(&quote.price, result_expo)?,
+            self.ema_price
+                .get_price_in_quote(&quote.ema_price, result_expo)?,
+        ))
+    }
+
+    /// Get a deterministic SHA-256 digest of the content of this price feed.
+    ///
+    /// The digest covers the id and the price, confidence and exponent of both the price and the
+    /// EMA price. Publish times are deliberately excluded, so two feeds that only differ in their
+    /// timestamps produce the same digest. This makes the result suitable as a caching key.
+    #[cfg(feature = "content-hash")]
+    pub fn content_hash(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(self.id.to_bytes());
+        for price in [self.price, self.ema_price].iter() {
+            hasher.update(price.price.to_le_bytes());
+            hasher.update(price.conf.to_le_bytes());
+            hasher.update(price.expo.to_le_bytes());
+        }
+        hasher.finalize().into()
+    }
 }
+
+/// Check that `feeds` are exactly the feeds identified by `expected`, in the same order.
+///
+/// Returns the first expected id that is mismatched or missing from `feeds`. If `feeds` has more
+/// entries than `expected`, the id of the first unexpected feed is returned.
+pub fn validate_feed_ids(
+    feeds: &[PriceFeed],
+    expected: &[PriceIdentifier],
+) -> Result<(), PriceIdentifier> {
+    for (i, expected_id) in expected.iter().enumerate() {
+        match feeds.get(i) {
+            Some(feed) if feed.id == *expected_id => {}
+            _ => return Err(*expected_id),
+        }
+    }
+
+    if let Some(feed) = feeds.get(expected.len()) {
+        return Err(feed.id);
+    }
+
+    Ok(())
+}
+
+/// Get the smallest lower end of the confidence interval, i.e., `price - conf`, across the feeds in
+/// `feeds` that were updated within `age` seconds of `current_time`, with exponent `result_expo`.
+///
+/// Stale feeds are ignored. Returns `None` if no feed is fresh, or if the bound of a fresh feed
+/// cannot be represented with the requested exponent. The returned `Price` has a confidence of 0
+/// and the publish time of the binding feed.
+pub fn min_conservative_price(
+    feeds: &[PriceFeed],
+    current_time: UnixTimestamp,
+    age: DurationInSeconds,
+    result_expo: i32,
+) -> Option<Price> {
+    let mut min_price: Option<Price> = None;
+
+    for feed in feeds {
+        let price = match feed.get_price_no_older_than(current_time, age) {
+            Some(price) => price,
+            None => continue,
+        };
+
+        let price = Price {
+            price: price.price.checked_sub(i64::try_from(price.conf).ok()?)?,
+            conf: 0,
+            ..price
+        }
+        .scale_to_exponent(result_expo)?;
+
+        match min_price {
+            Some(min) if min.price <= price.price => {}
+            _ => min_price = Some(price),
+        }
+    }
+
+    min_price
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -239,6 +670,42 @@ mod test {
         assert_eq!(p.get_price_unchecked().price, 1_000_000_000_000_000_123);
     }
 
+    #[test]
+    pub fn test_ser_price_numeric() {
+        let price = Price {
+            price:        12345,
+            conf:         67,
+            expo:         -2,
+            publish_time: 100,
+        };
+
+        let price_json = serde_json::to_value(PriceNumeric(price)).unwrap();
+        assert_eq!(price_json["price"].as_i64(), Some(12345));
+        assert_eq!(price_json["conf"].as_u64(), Some(67));
+        let deser: PriceNumeric = serde_json::from_value(price_json).unwrap();
+        assert_eq!(deser.0, price);
+
+        // The default representation still uses strings, even for huge values
+        let price = Price {
+            conf: u64::MAX,
+            ..price
+        };
+        let price_json = serde_json::to_value(price).unwrap();
+        assert_eq!(price_json["price"].as_str(), Some("12345"));
+        assert_eq!(price_json["conf"].as_str(), Some("18446744073709551615"));
+        let deser: Price = serde_json::from_value(price_json.clone()).unwrap();
+        assert_eq!(deser, price);
+
+        // Both representations accept either form
+        let deser: PriceNumeric = serde_json::from_value(price_json).unwrap();
+        assert_eq!(deser.0, price);
+        let deser: Price = serde_json::from_str(
+            r#"{"price":12345,"conf":18446744073709551615,"expo":-2,"publish_time":100}"#,
+        )
+        .unwrap();
+        assert_eq!(deser, price);
+    }
+
     #[test]
     pub fn test_ser_id_length_32_bytes() {
         let mut price_feed = PriceFeed::default();
@@ -259,6 +726,459 @@ mod test {
         assert!(serde_json::from_value::<PriceFeed>(price_feed_json).is_err());
     }
 
+    #[test]
+    pub fn test_get_price_no_older_than_strict() {
+        let price_feed = PriceFeed::new(
+            Identifier::default(),
+            Price {
+                publish_time: 100,
+                ..Price::default()
+            },
+            Price::default(),
+        );
+        assert_eq!(
+            price_feed.get_price_no_older_than_strict(110, 10),
+            price_feed.get_price_no_older_than(110, 10)
+        );
+        assert!(price_feed.get_price_no_older_than_strict(110, 10).is_some());
+        assert_eq!(price_feed.get_price_no_older_than_strict(111, 10), None);
+
+        // A zero publish time is never considered fresh, even if it is within `age`.
+        let unpublished_feed = PriceFeed::default();
+        assert!(unpublished_feed.get_price_no_older_than(5, 10).is_some());
+        assert_eq!(unpublished_feed.get_price_no_older_than_strict(5, 10), None);
+        assert_eq!(unpublished_feed.get_price_no_older_than_strict(0, 10), None);
+    }
+
+    #[test]
+    pub fn test_get_price_no_older_than_bounded() {
+        let price_feed = PriceFeed::new(
+            Identifier::default(),
+            Price {
+                publish_time: 100,
+                ..Price::default()
+            },
+            Price::default(),
+        );
+
+        // Published in the past
+        assert_eq!(
+            price_feed.get_price_no_older_than_bounded(110, 10, 0),
+            Some(price_feed.get_price_unchecked())
+        );
+        assert_eq!(price_feed.get_price_no_older_than_bounded(111, 10, 0), None);
+
+        // Published slightly ahead of the current time
+        assert_eq!(
+            price_feed.get_price_no_older_than_bounded(98, 10, 2),
+            Some(price_feed.get_price_unchecked())
+        );
+        assert_eq!(price_feed.get_price_no_older_than_bounded(98, 10, 1), None);
+
+        // Published far in the future, which `get_price_no_older_than` accepts
+        assert!(price_feed.get_price_no_older_than(50, 60).is_some());
+        assert_eq!(price_feed.get_price_no_older_than_bounded(50, 60, 5), None);
+        assert_eq!(price_feed.get_price_no_older_than_bounded(99, 60, 0), None);
+
+        // Extreme values don't overflow
+        assert_eq!(
+            price_feed.get_price_no_older_than_bounded(i64::MIN, u64::MAX, 0),
+            None
+        );
+        assert_eq!(
+            price_feed.get_price_no_older_than_bounded(i64::MIN, 0, u64::MAX),
+            Some(price_feed.get_price_unchecked())
+        );
+        assert_eq!(
+            price_feed.get_price_no_older_than_bounded(i64::MAX, u64::MAX, 0),
+            Some(price_feed.get_price_unchecked())
+        );
+    }
+
+    #[test]
+    pub fn test_get_price_no_older_than_with_conf_bounds() {
+        let price_feed = PriceFeed::new(
+            Identifier::default(),
+            Price {
+                price:        10000,
+                conf:         100,
+                expo:         -2,
+                publish_time: 100,
+            },
+            Price::default(),
+        );
+
+        assert_eq!(
+            price_feed.get_price_no_older_than_with_conf_lower(110, 10),
+            Some(Price {
+                price:        9900,
+                conf:         0,
+                expo:         -2,
+                publish_time: 100,
+            })
+        );
+        assert_eq!(
+            price_feed.get_price_no_older_than_with_conf_upper(110, 10),
+            Some(Price {
+                price:        10100,
+                conf:         0,
+                expo:         -2,
+                publish_time: 100,
+            })
+        );
+
+        // Same age check as `get_price_no_older_than`
+        assert_eq!(
+            price_feed.get_price_no_older_than_with_conf_lower(111, 10),
+            None
+        );
+        assert_eq!(
+            price_feed.get_price_no_older_than_with_conf_upper(89, 10),
+            None
+        );
+
+        // Overflow
+        let low_price_feed = PriceFeed::new(
+            Identifier::default(),
+            Price {
+                price:        i64::MIN + 1,
+                conf:         2,
+                expo:         -2,
+                publish_time: 100,
+            },
+            Price::default(),
+        );
+        assert_eq!(
+            low_price_feed.get_price_no_older_than_with_conf_lower(100, 10),
+            None
+        );
+        assert!(low_price_feed
+            .get_price_no_older_than_with_conf_upper(100, 10)
+            .is_some());
+
+        let high_price_feed = PriceFeed::new(
+            Identifier::default(),
+            Price {
+                price:        i64::MAX - 1,
+                conf:         2,
+                expo:         -2,
+                publish_time: 100,
+            },
+            Price::default(),
+        );
+        assert_eq!(
+            high_price_feed.get_price_no_older_than_with_conf_upper(100, 10),
+            None
+        );
+
+        let wide_price_feed = PriceFeed::new(
+            Identifier::default(),
+            Price {
+                price:        0,
+                conf:         u64::MAX,
+                expo:         -2,
+                publish_time: 100,
+            },
+            Price::default(),
+        );
+        assert_eq!(
+            wide_price_feed.get_price_no_older_than_with_conf_lower(100, 10),
+            None
+        );
+        assert_eq!(
+            wide_price_feed.get_price_no_older_than_with_conf_upper(100, 10),
+            None
+        );
+    }
+
+    #[test]
+    pub fn test_collateral_value() {
+        let price_feed = PriceFeed::new(
+            Identifier::default(),
+            Price {
+                price:        10000,
+                conf:         100,
+                expo:         -2,
+                publish_time: 100,
+            },
+            Price::default(),
+        );
+
+        // (100.00 - 1.00) * 5 * 80% = 396.00
+        assert_eq!(
+            price_feed.collateral_value(5, 0, 8000, 110, 60, -2),
+            Some(Price {
+                price:        39600,
+                conf:         0,
+                expo:         -2,
+                publish_time: 100,
+            })
+        );
+        // (100.00 - 1.00) * 0.5 * 100% = 49.50
+        assert_eq!(
+            price_feed.collateral_value(5, -1, 10000, 110, 60, -2),
+            Some(Price {
+                price:        4950,
+                conf:         0,
+                expo:         -2,
+                publish_time: 100,
+            })
+        );
+        assert_eq!(
+            price_feed
+                .collateral_value(5, 0, 0, 110, 60, -2)
+                .map(|price| price.price),
+            Some(0)
+        );
+
+        // Stale price
+        assert_eq!(price_feed.collateral_value(5, 0, 8000, 161, 60, -2), None);
+
+        // Overflow
+        assert_eq!(price_feed.collateral_value(5, 0, 8000, 110, 60, -18), None);
+        assert_eq!(
+            price_feed.collateral_value(5, 0, u64::MAX, 110, 60, -2),
+            None
+        );
+        let wide_price_feed = PriceFeed::new(
+            Identifier::default(),
+            Price {
+                price:        i64::MIN,
+                conf:         1,
+                expo:         -2,
+                publish_time: 100,
+            },
+            Price::default(),
+        );
+        assert_eq!(
+            wide_price_feed.collateral_value(5, 0, 8000, 110, 60, -2),
+            None
+        );
+    }
+
+    #[test]
+    pub fn test_conservative_collateral_price() {
+        let price_feed = PriceFeed::new(
+            Identifier::default(),
+            Price {
+                price:        10000,
+                conf:         100,
+                expo:         -2,
+                publish_time: 100,
+            },
+            Price {
+                price:        9500,
+                conf:         50,
+                expo:         -2,
+                publish_time: 90,
+            },
+        );
+
+        // The EMA is the binding constraint: 95.00 - 0.50 < 100.00 - 1.00
+        assert_eq!(
+            price_feed.conservative_collateral_price(100, 60, -3),
+            Some(Price {
+                price:        94500,
+                conf:         0,
+                expo:         -3,
+                publish_time: 90,
+            })
+        );
+
+        // The spot price is the binding constraint: 100.00 - 10.00 < 95.00 - 0.50
+        let wide_price_feed = PriceFeed::new(
+            Identifier::default(),
+            Price {
+                conf: 1000,
+                ..price_feed.get_price_unchecked()
+            },
+            price_feed.get_ema_price_unchecked(),
+        );
+        assert_eq!(
+            wide_price_feed.conservative_collateral_price(100, 60, -2),
+            Some(Price {
+                price:        9000,
+                conf:         0,
+                expo:         -2,
+                publish_time: 100,
+            })
+        );
+
+        // Both prices must be fresh
+        assert_eq!(price_feed.conservative_collateral_price(155, 60, -2), None);
+        assert_eq!(price_feed.conservative_collateral_price(50, 45, -2), None);
+
+        // Overflow
+        assert_eq!(price_feed.conservative_collateral_price(100, 60, -18), None);
+    }
+
+    #[test]
+    pub fn test_get_feed_in_quote() {
+        let msol_usd = PriceFeed::new(
+            Identifier::new([1; 32]),
+            Price {
+                price:        11000,
+                conf:         110,
+                expo:         -2,
+                publish_time: 100,
+            },
+            Price {
+                price:        10500,
+                conf:         50,
+                expo:         -2,
+                publish_time: 90,
+            },
+        );
+        let sol_usd = PriceFeed::new(
+            Identifier::new([2; 32]),
+            Price {
+                price:        1000,
+                conf:         0,
+                expo:         -1,
+                publish_time: 95,
+            },
+            Price {
+                price:        700,
+                conf:         0,
+                expo:         -1,
+                publish_time: 80,
+            },
+        );
+        let msol_sol_id = Identifier::new([3; 32]);
+
+        let msol_sol = msol_usd
+            .get_feed_in_quote(&sol_usd, -3, msol_sol_id)
+            .unwrap();
+        assert_eq!(msol_sol.id, msol_sol_id);
+        assert_eq!(
+            msol_sol.get_price_unchecked(),
+            msol_usd
+                .get_price_unchecked()
+                .get_price_in_quote(&sol_usd.get_price_unchecked(), -3)
+                .unwrap()
+        );
+        assert_eq!(
+            msol_sol.get_ema_price_unchecked(),
+            msol_usd
+                .get_ema_price_unchecked()
+                .get_price_in_quote(&sol_usd.get_ema_price_unchecked(), -3)
+                .unwrap()
+        );
+        // 110.00 / 100.0 and 105.00 / 70.0
+        assert_eq!(msol_sol.get_price_unchecked().price, 1100);
+        assert_eq!(msol_sol.get_price_unchecked().conf, 11);
+        assert_eq!(msol_sol.get_ema_price_unchecked().price, 1500);
+
+        // Dividing by a zero EMA price fails
+        let zero_ema_feed = PriceFeed::new(
+            sol_usd.id,
+            sol_usd.get_price_unchecked(),
+            Price {
+                price: 0,
+                ..sol_usd.get_ema_price_unchecked()
+            },
+        );
+        assert_eq!(
+            msol_usd.get_feed_in_quote(&zero_ema_feed, -3, msol_sol_id),
+            None
+        );
+    }
+
+    #[test]
+    pub fn test_price_feed_scale_to_exponent() {
+        let price_feed = PriceFeed::new(
+            Identifier::new([1; 32]),
+            Price {
+                price:        10000,
+                conf:         100,
+                expo:         -2,
+                publish_time: 100,
+            },
+            Price {
+                price:        9500,
+                conf:         50,
+                expo:         -3,
+                publish_time: 90,
+            },
+        );
+
+        let scaled = price_feed.scale_to_exponent(-4).unwrap();
+        assert_eq!(scaled.id, price_feed.id);
+        assert_eq!(
+            scaled.get_price_unchecked(),
+            Price {
+                price:        1000000,
+                conf:         10000,
+                expo:         -4,
+                publish_time: 100,
+            }
+        );
+        assert_eq!(
+            scaled.get_ema_price_unchecked(),
+            Price {
+                price:        95000,
+                conf:         500,
+                expo:         -4,
+                publish_time: 90,
+            }
+        );
+
+        // Failing to rescale either price fails
+        let large_ema_feed = PriceFeed::new(
+            price_feed.id,
+            price_feed.get_price_unchecked(),
+            Price {
+                price: i64::MAX,
+                ..price_feed.get_ema_price_unchecked()
+            },
+        );
+        assert!(large_ema_feed
+            .get_price_unchecked()
+            .scale_to_exponent(-4)
+            .is_some());
+        assert_eq!(large_ema_feed.scale_to_exponent(-4), None);
+
+        let large_price_feed = PriceFeed::new(
+            price_feed.id,
+            Price {
+                price: i64::MAX,
+                ..price_feed.get_price_unchecked()
+            },
+            price_feed.get_ema_price_unchecked(),
+        );
+        assert_eq!(large_price_feed.scale_to_exponent(-4), None);
+    }
+
+    #[test]
+    pub fn test_validate_feed_ids() {
+        let ids = [
+            Identifier::new([1; 32]),
+            Identifier::new([2; 32]),
+            Identifier::new([3; 32]),
+        ];
+        let feeds: Vec<PriceFeed> = ids
+            .iter()
+            .map(|id| PriceFeed::new(*id, Price::default(), Price::default()))
+            .collect();
+
+        assert_eq!(validate_feed_ids(&feeds, &ids), Ok(()));
+        assert_eq!(validate_feed_ids(&[], &[]), Ok(()));
+
+        // Mismatched id
+        let mismatched = [ids[0], Identifier::new([4; 32]), ids[2]];
+        assert_eq!(validate_feed_ids(&feeds, &mismatched), Err(mismatched[1]));
+
+        // Wrong order
+        let reordered = [ids[0], ids[2], ids[1]];
+        assert_eq!(validate_feed_ids(&feeds, &reordered), Err(ids[2]));
+
+        // Missing feed
+        assert_eq!(validate_feed_ids(&feeds[..2], &ids), Err(ids[2]));
+
+        // Unexpected feed
+        assert_eq!(validate_feed_ids(&feeds, &ids[..2]), Err(ids[2]));
+    }
+
     #[test]
     pub fn test_identifier_from_hex_ok() {
         let id = Identifier::from_hex(
@@ -280,6 +1200,105 @@ mod test {
         );
     }
 
+    #[test]
+    pub fn test_identifier_from_str() {
+        let hex = "63f341689d98a12ef60a5cff1d7f85c70a9e17bf1575f0e7c0b2512d48b1c8b3";
+        let expected = Identifier::from_hex(hex).unwrap();
+
+        assert_eq!(hex.parse::<Identifier>(), Ok(expected));
+        assert_eq!(format!("0x{}", hex).parse::<Identifier>(), Ok(expected));
+        assert_eq!(
+            "0x0a".parse::<Identifier>(),
+            Err(FromHexError::InvalidStringLength)
+        );
+    }
+
+    #[test]
+    pub fn test_identifier_try_from_slice() {
+        let bytes: Vec<u8> = (0..33).collect();
+
+        let id = Identifier::try_from(&bytes[..32]).unwrap();
+        assert_eq!(id.as_ref(), &bytes[..32]);
+
+        assert_eq!(
+            Identifier::try_from(&bytes[..31]),
+            Err(FromHexError::InvalidStringLength)
+        );
+        assert_eq!(
+            Identifier::try_from(&bytes[..]),
+            Err(FromHexError::InvalidStringLength)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "content-hash")]
+    pub fn test_content_hash_ignores_publish_time() {
+        let price_feed = PriceFeed::new(
+            Identifier::new([1; 32]),
+            Price {
+                price:        100,
+                conf:         10,
+                expo:         -2,
+                publish_time: 1,
+            },
+            Price {
+                price:        110,
+                conf:         12,
+                expo:         -2,
+                publish_time: 1,
+            },
+        );
+
+        let mut later_feed = price_feed;
+        later_feed.price.publish_time = 100;
+        later_feed.ema_price.publish_time = 200;
+        assert_eq!(price_feed.content_hash(), later_feed.content_hash());
+
+        let mut other_price_feed = price_feed;
+        other_price_feed.ema_price.conf = 13;
+        assert_ne!(price_feed.content_hash(), other_price_feed.content_hash());
+
+        let mut other_id_feed = price_feed;
+        other_id_feed.id = Identifier::new([2; 32]);
+        assert_ne!(price_feed.content_hash(), other_id_feed.content_hash());
+    }
+
+    #[test]
+    #[cfg(feature = "base58")]
+    pub fn test_identifier_base58() {
+        // SOL/USD price account on Solana mainnet
+        let base58 = "H6ARHf6YXhGYeQfUzQNGk6rDNnLBQKrenN712K4AQJEG";
+        let id = Identifier::from_hex(
+            "ef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d",
+        )
+        .unwrap();
+
+        assert_eq!(Identifier::from_base58(base58), Ok(id));
+        assert_eq!(id.to_base58(), base58);
+
+        let id = Identifier::new([7; 32]);
+        assert_eq!(Identifier::from_base58(&id.to_base58()), Ok(id));
+
+        assert_eq!(
+            Identifier::from_base58("H6ARHf6YXhGYeQfUzQNGk6rDNnLBQKren"),
+            Err(Base58Error::InvalidLength(24))
+        );
+        assert!(matches!(
+            Identifier::from_base58("0OIl"),
+            Err(Base58Error::Decode(_))
+        ));
+    }
+
+    #[test]
+    pub fn test_identifier_to_hex_short() {
+        let id = Identifier::from_hex(
+            "63f341689d98a12ef60a5cff1d7f85c70a9e17bf1575f0e7c0b2512d48b1c8b3",
+        )
+        .unwrap();
+        assert_eq!(id.to_hex_short(), "0x63f3…c8b3");
+        assert_eq!(Identifier::default().to_hex_short(), "0x0000…0000");
+    }
+
     #[test]
     pub fn test_identifier_debug_fmt() {
         let mut id = Identifier::default();
@@ -303,4 +1322,301 @@ mod test {
             "0x0a00000000000000000000000000000000000000000000000000000000000000"
         );
     }
+
+    #[test]
+    pub fn test_publish_times() {
+        let feed = PriceFeed::new(
+            PriceIdentifier::new([1; 32]),
+            Price {
+                price:        100,
+                conf:         1,
+                expo:         -2,
+                publish_time: 1_000,
+            },
+            Price {
+                price:        90,
+                conf:         2,
+                expo:         -2,
+                publish_time: 950,
+            },
+        );
+
+        assert_eq!(feed.publish_time(), 1_000);
+        assert_eq!(feed.ema_publish_time(), 950);
+        assert_eq!(feed.get_price_unchecked().publish_time, 1_000);
+        assert_eq!(feed.get_ema_price_unchecked().publish_time, 950);
+    }
+
+    #[test]
+    pub fn test_get_price_no_older_than_default() {
+        let price = Price {
+            price:        100,
+            conf:         1,
+            expo:         -2,
+            publish_time: 1_000,
+        };
+        let feed = PriceFeed::new(PriceIdentifier::new([1; 32]), price, price);
+
+        assert_eq!(
+            feed.get_price_no_older_than_default(1_000 + DEFAULT_VALID_PERIOD_SECS as i64),
+            Some(price)
+        );
+        assert_eq!(
+            feed.get_price_no_older_than_default(1_001 + DEFAULT_VALID_PERIOD_SECS as i64),
+            None
+        );
+    }
+
+    #[test]
+    pub fn test_require_price_no_older_than() {
+        let price = Price {
+            price:        100,
+            conf:         1,
+            expo:         -2,
+            publish_time: 1_000,
+        };
+        let feed = PriceFeed::new(PriceIdentifier::new([1; 32]), price, price);
+
+        assert_eq!(feed.require_price_no_older_than(1_010, 10), Ok(price));
+        assert_eq!(
+            feed.require_price_no_older_than(1_011, 10),
+            Err(OracleError::NoneEncountered)
+        );
+    }
+
+    #[test]
+    pub fn test_min_conservative_price() {
+        let feed = |id: u8, price: i64, conf: u64, expo: i32, publish_time: UnixTimestamp| {
+            let price = Price {
+                price,
+                conf,
+                expo,
+                publish_time,
+            };
+            PriceFeed::new(PriceIdentifier::new([id; 32]), price, price)
+        };
+
+        let feeds = [
+            // 99.00 - 1.00, fresh
+            feed(1, 9_900, 100, -2, 1_000),
+            // 97.5 - 0.5, fresh
+            feed(2, 975, 5, -1, 995),
+            // 50.00 - 1.00, stale
+            feed(3, 5_000, 100, -2, 900),
+        ];
+
+        assert_eq!(
+            min_conservative_price(&feeds, 1_000, 10, -3),
+            Some(Price {
+                price:        97_000,
+                conf:         0,
+                expo:         -3,
+                publish_time: 995,
+            })
+        );
+
+        // Once the stale feed is fresh enough, it is the binding one.
+        assert_eq!(
+            min_conservative_price(&feeds, 1_000, 100, -2),
+            Some(Price {
+                price:        4_900,
+                conf:         0,
+                expo:         -2,
+                publish_time: 900,
+            })
+        );
+
+        // Only stale feeds
+        assert_eq!(min_conservative_price(&feeds[2..], 1_000, 10, -2), None);
+        assert_eq!(min_conservative_price(&[], 1_000, 10, -2), None);
+
+        // The bound of a fresh feed overflows
+        let overflowing = [feeds[0], feed(4, i64::MIN, 1, -2, 1_000)];
+        assert_eq!(min_conservative_price(&overflowing, 1_000, 10, -2), None);
+    }
+
+    #[test]
+    pub fn test_is_newer_than() {
+        let price = Price {
+            price:        100,
+            conf:         1,
+            expo:         -2,
+            publish_time: 1_000,
+        };
+        let feed = PriceFeed::new(PriceIdentifier::new([1; 32]), price, price);
+
+        assert!(feed.is_newer_than(999));
+        assert!(!feed.is_newer_than(1_000));
+        assert!(!feed.is_newer_than(1_001));
+    }
+
+    #[test]
+    pub fn test_get_ema_price_no_older_than_checked() {
+        let feed_with_ema = |price: i64, conf: u64| {
+            PriceFeed::new(
+                Identifier::default(),
+                Price::default(),
+                Price {
+                    price,
+                    conf,
+                    expo: -2,
+                    publish_time: 100,
+                },
+            )
+        };
+
+        // 1% confidence
+        let price_feed = feed_with_ema(10000, 100);
+        assert!(price_feed.ema_conf_is_reliable(100));
+        assert!(!price_feed.ema_conf_is_reliable(99));
+        assert_eq!(
+            price_feed.get_ema_price_no_older_than_checked(110, 10, 100),
+            Some(price_feed.get_ema_price_unchecked())
+        );
+        assert_eq!(
+            price_feed.get_ema_price_no_older_than_checked(110, 10, 99),
+            None
+        );
+        // Same age check as `get_ema_price_no_older_than`
+        assert_eq!(
+            price_feed.get_ema_price_no_older_than_checked(111, 10, 100),
+            None
+        );
+
+        // Negative prices use the magnitude of the price
+        let price_feed = feed_with_ema(-10000, 100);
+        assert!(price_feed.ema_conf_is_reliable(100));
+
+        // Zero confidence
+        let price_feed = feed_with_ema(10000, 0);
+        assert!(!price_feed.ema_conf_is_reliable(10_000));
+        assert_eq!(
+            price_feed.get_ema_price_no_older_than_checked(110, 10, 10_000),
+            None
+        );
+
+        // Wide confidence
+        let price_feed = feed_with_ema(10000, 20000);
+        assert!(!price_feed.ema_conf_is_reliable(10_000));
+        assert!(price_feed.ema_conf_is_reliable(20_000));
+        assert_eq!(
+            price_feed.get_ema_price_no_older_than_checked(110, 10, 500),
+            None
+        );
+
+        // No overflow for extreme values
+        let price_feed = feed_with_ema(i64::MIN, u64::MAX);
+        assert!(!price_feed.ema_conf_is_reliable(10_000));
+        assert!(price_feed.ema_conf_is_reliable(u64::MAX));
+    }
+
+    #[test]
+    pub fn test_get_price_no_older_than_with_max_conf_ratio() {
+        let feed_with_price = |price: i64, conf: u64| {
+            PriceFeed::new(
+                Identifier::default(),
+                Price {
+                    price,
+                    conf,
+                    expo: -2,
+                    publish_time: 100,
+                },
+                Price::default(),
+            )
+        };
+
+        // Tight confidence passes
+        let price_feed = feed_with_price(10000, 10);
+        assert_eq!(
+            price_feed.get_price_no_older_than_with_max_conf_ratio(110, 10, 0.01),
+            Some(price_feed.get_price_unchecked())
+        );
+        // Same age check as `get_price_no_older_than`
+        assert_eq!(
+            price_feed.get_price_no_older_than_with_max_conf_ratio(111, 10, 0.01),
+            None
+        );
+
+        // Wide confidence is rejected
+        let price_feed = feed_with_price(-10000, 500);
+        assert_eq!(
+            price_feed.get_price_no_older_than_with_max_conf_ratio(110, 10, 0.01),
+            None
+        );
+        assert_eq!(
+            price_feed.get_price_no_older_than_with_max_conf_ratio(110, 10, 0.05),
+            Some(price_feed.get_price_unchecked())
+        );
+
+        // Zero price
+        let price_feed = feed_with_price(0, 0);
+        assert_eq!(
+            price_feed.get_price_no_older_than_with_max_conf_ratio(110, 10, f64::MAX),
+            None
+        );
+    }
+
+    #[test]
+    pub fn test_get_prices_no_older_than() {
+        let price = Price {
+            price:        10000,
+            conf:         10,
+            expo:         -2,
+            publish_time: 100,
+        };
+        let ema_price = Price {
+            price:        9900,
+            conf:         20,
+            expo:         -2,
+            publish_time: 90,
+        };
+        let price_feed = PriceFeed::new(Identifier::default(), price, ema_price);
+
+        // Both fresh
+        assert_eq!(
+            price_feed.get_prices_no_older_than(100, 10),
+            Some((price, ema_price))
+        );
+
+        // One stale
+        assert_eq!(price_feed.get_prices_no_older_than(101, 10), None);
+        assert_eq!(price_feed.get_prices_no_older_than(89, 10), None);
+
+        // Both stale
+        assert_eq!(price_feed.get_prices_no_older_than(200, 10), None);
+    }
+
+    #[test]
+    pub fn test_price_with_id_no_older_than() {
+        let feeds: Vec<PriceFeed> = (1..=3u8)
+            .map(|i| {
+                PriceFeed::new(
+                    Identifier::new([i; 32]),
+                    Price {
+                        price:        i as i64 * 100,
+                        conf:         1,
+                        expo:         -2,
+                        publish_time: 100 + i as i64,
+                    },
+                    Price::default(),
+                )
+            })
+            .collect();
+
+        assert_eq!(feeds[0].id(), Identifier::new([1; 32]));
+        assert_eq!(
+            feeds[0].price_with_id_no_older_than(110, 10),
+            Some((Identifier::new([1; 32]), feeds[0].get_price_unchecked()))
+        );
+
+        // The stale first feed is left out of the map
+        let prices: std::collections::HashMap<PriceIdentifier, Price> = feeds
+            .iter()
+            .filter_map(|feed| feed.price_with_id_no_older_than(112, 10))
+            .collect();
+        assert_eq!(prices.len(), 2);
+        assert_eq!(prices.get(&Identifier::new([1; 32])), None);
+        assert_eq!(prices[&Identifier::new([2; 32])].price, 200);
+        assert_eq!(prices[&Identifier::new([3; 32])].price, 300);
+    }
 }
@@ -9,6 +9,8 @@ use schemars::JsonSchema;
 
 use crate::{
     utils,
+    DurationInSeconds,
+    OracleError,
     UnixTimestamp,
 };
 
@@ -17,6 +19,13 @@ const PD_EXPO: i32 = -9;
 const PD_SCALE: u64 = 1_000_000_000;
 const MAX_PD_V_U64: u64 = (1 << 28) - 1;
 
+// Largest absolute exponent that `Display` writes as a plain decimal number. Larger ones use
+// scientific notation, so that formatting doesn't write (and allocate) billions of zeros.
+const MAX_DISPLAY_DECIMAL_EXPO: u32 = 32;
+
+// Scale of the weights returned by `Price::inverse_variance_weight`
+const INVERSE_VARIANCE_SCALE: u128 = 1_000_000_000_000_000_000_000_000_000_000_000_000;
+
 /// A price with a degree of uncertainty at a certain time, represented as a price +- a confidence
 /// interval.
 ///
@@ -68,7 +77,48 @@ pub struct Price {
     pub publish_time: UnixTimestamp,
 }
 
+/// A `Price` that serializes its `price` and `conf` as JSON numbers instead of strings.
+///
+/// `Price` serializes these fields as strings by default, since JSON numbers above 2^53 lose
+/// precision in many JSON implementations (e.g., JavaScript). Use this wrapper only for tools that
+/// expect numbers and values that are known to be small enough. Deserialization accepts both the
+/// number and the string form, like `Price`.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PriceNumeric(#[serde(with = "PriceNumericDef")] pub Price);
+
+/// Serde definition of `Price` used by `PriceNumeric`.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(remote = "Price")]
+#[allow(dead_code)]
+struct PriceNumericDef {
+    #[serde(deserialize_with = "utils::as_string::deserialize")]
+    price:        i64,
+    #[serde(deserialize_with = "utils::as_string::deserialize")]
+    conf:         u64,
+    expo:         i32,
+    publish_time: UnixTimestamp,
+}
+
 impl Price {
+    /// Deserialize a `Price` from the legacy `PriceConf` representation, which has no publish time.
+    /// The publish time of the result is set to 0.
+    ///
+    /// This function can be used as a field attribute, i.e.,
+    /// `#[serde(deserialize_with = "Price::from_price_conf_json")]`, or called directly with a
+    /// deserializer such as a `serde_json::Value` holding the legacy payload.
+    pub fn from_price_conf_json<'de, D>(deserializer: D) -> Result<Price, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let price_conf = <PriceConf as serde::Deserialize>::deserialize(deserializer)?;
+        Ok(Price {
+            price:        price_conf.price,
+            conf:         price_conf.conf,
+            expo:         price_conf.expo,
+            publish_time: 0,
+        })
+    }
+
     /// Get the current price of this account in a different quote currency.
     ///
     /// If this account represents the price of the product X/Z, and `quote` represents the price
@@ -91,6 +141,22 @@ impl Price {
         self.div(quote)?.scale_to_exponent(result_expo)
     }
 
+    /// Get the reciprocal of this price, i.e., `1 / self`, with exponent `result_expo`.
+    ///
+    /// This inverts the quote direction of a price, e.g., it turns a USD/JPY price into a JPY/USD
+    /// price. The uncertainty is propagated as in `div`. Returns `None` if the price is zero or
+    /// if the result cannot be represented with the requested exponent.
+    pub fn reciprocal(&self, result_expo: i32) -> Option<Price> {
+        Price {
+            price:        1,
+            conf:         0,
+            expo:         0,
+            publish_time: self.publish_time,
+        }
+        .div(self)?
+        .scale_to_exponent(result_expo)
+    }
+
     /// Get the valuation of a collateral position according to:
     /// 1. the net amount currently deposited (across the protocol)
     /// 2. the deposits endpoint for the affine combination (across the protocol)
@@ -125,45 +191,79 @@ impl Price {
         rate_discount_final: u64,
         discount_exponent: i32,
     ) -> Option<Price> {
+        self.try_get_collateral_valuation_price(
+            deposits,
+            deposits_endpoint,
+            rate_discount_initial,
+            rate_discount_final,
+            discount_exponent,
+        )
+        .ok()
+    }
+
+    /// Same as `get_collateral_valuation_price`, but returns an `OracleError` describing why the
+    /// valuation failed:
+    /// - `InitialDiscountExceedsFinalDiscount` if `rate_discount_initial < rate_discount_final`
+    /// - `I64ConversionError` if one of the `u64` arguments is larger than `i64::MAX`
+    /// - `ZeroEndpoint` if `deposits_endpoint` is 0
+    /// - `Overflow` if the valuation cannot be represented with the exponent of this price
+    pub fn try_get_collateral_valuation_price(
+        &self,
+        deposits: u64,
+        deposits_endpoint: u64,
+        rate_discount_initial: u64,
+        rate_discount_final: u64,
+        discount_exponent: i32,
+    ) -> Result<Price, OracleError> {
         // valuation price should not increase as amount of collateral grows, so
         // rate_discount_initial should >= rate_discount_final
         if rate_discount_initial < rate_discount_final {
-            return None;
+            return Err(OracleError::InitialDiscountExceedsFinalDiscount);
         }
 
         // get price versions of discounts
         let initial_percentage = Price {
-            price:        i64::try_from(rate_discount_initial).ok()?,
+            price:        i64::try_from(rate_discount_initial)
+                .map_err(|_| OracleError::I64ConversionError)?,
             conf:         0,
             expo:         discount_exponent,
             publish_time: 0,
         };
         let final_percentage = Price {
-            price:        i64::try_from(rate_discount_final).ok()?,
+            price:        i64::try_from(rate_discount_final)
+                .map_err(|_| OracleError::I64ConversionError)?,
             conf:         0,
             expo:         discount_exponent,
             publish_time: 0,
         };
+        let deposits_endpoint =
+            i64::try_from(deposits_endpoint).map_err(|_| OracleError::I64ConversionError)?;
+        let deposits = i64::try_from(deposits).map_err(|_| OracleError::I64ConversionError)?;
+        if deposits_endpoint == 0 {
+            return Err(OracleError::ZeroEndpoint);
+        }
 
         // get the interpolated discount as a price
         let discount_interpolated = Price::affine_combination(
             0,
             initial_percentage,
-            i64::try_from(deposits_endpoint).ok()?,
+            deposits_endpoint,
             final_percentage,
-            i64::try_from(deposits).ok()?,
+            deposits,
             -9,
-        )?;
+        )
+        .ok_or(OracleError::Overflow)?;
 
         let conf_orig = self.conf;
         let expo_orig = self.expo;
 
         // get price discounted, convert back to the original exponents we received the price in
         let price_discounted = self
-            .mul(&discount_interpolated)?
-            .scale_to_exponent(expo_orig)?;
+            .try_mul(&discount_interpolated)?
+            .scale_to_exponent(expo_orig)
+            .ok_or(OracleError::Overflow)?;
 
-        return Some(Price {
+        return Ok(Price {
             price:        price_discounted.price,
             conf:         conf_orig,
             expo:         price_discounted.expo,
@@ -205,45 +305,79 @@ impl Price {
         rate_premium_final: u64,
         premium_exponent: i32,
     ) -> Option<Price> {
+        self.try_get_borrow_valuation_price(
+            borrows,
+            borrows_endpoint,
+            rate_premium_initial,
+            rate_premium_final,
+            premium_exponent,
+        )
+        .ok()
+    }
+
+    /// Same as `get_borrow_valuation_price`, but returns an `OracleError` describing why the
+    /// valuation failed:
+    /// - `InitialPremiumExceedsFinalPremium` if `rate_premium_initial > rate_premium_final`
+    /// - `I64ConversionError` if one of the `u64` arguments is larger than `i64::MAX`
+    /// - `ZeroEndpoint` if `borrows_endpoint` is 0
+    /// - `Overflow` if the valuation cannot be represented with the exponent of this price
+    pub fn try_get_borrow_valuation_price(
+        &self,
+        borrows: u64,
+        borrows_endpoint: u64,
+        rate_premium_initial: u64,
+        rate_premium_final: u64,
+        premium_exponent: i32,
+    ) -> Result<Price, OracleError> {
         // valuation price should not decrease as amount of borrow grows, so rate_premium_initial
         // should <= rate_premium_final
         if rate_premium_initial > rate_premium_final {
-            return None;
+            return Err(OracleError::InitialPremiumExceedsFinalPremium);
         }
 
         // get price versions of premiums
         let initial_percentage = Price {
-            price:        i64::try_from(rate_premium_initial).ok()?,
+            price:        i64::try_from(rate_premium_initial)
+                .map_err(|_| OracleError::I64ConversionError)?,
             conf:         0,
             expo:         premium_exponent,
             publish_time: 0,
         };
         let final_percentage = Price {
-            price:        i64::try_from(rate_premium_final).ok()?,
+            price:        i64::try_from(rate_premium_final)
+                .map_err(|_| OracleError::I64ConversionError)?,
             conf:         0,
             expo:         premium_exponent,
             publish_time: 0,
         };
+        let borrows_endpoint =
+            i64::try_from(borrows_endpoint).map_err(|_| OracleError::I64ConversionError)?;
+        let borrows = i64::try_from(borrows).map_err(|_| OracleError::I64ConversionError)?;
+        if borrows_endpoint == 0 {
+            return Err(OracleError::ZeroEndpoint);
+        }
 
         // get the interpolated premium as a price
         let premium_interpolated = Price::affine_combination(
             0,
             initial_percentage,
-            i64::try_from(borrows_endpoint).ok()?,
+            borrows_endpoint,
             final_percentage,
-            i64::try_from(borrows).ok()?,
+            borrows,
             -9,
-        )?;
+        )
+        .ok_or(OracleError::Overflow)?;
 
         let conf_orig = self.conf;
         let expo_orig = self.expo;
 
         // get price premium, convert back to the original exponents we received the price in
         let price_premium = self
-            .mul(&premium_interpolated)?
-            .scale_to_exponent(expo_orig)?;
+            .try_mul(&premium_interpolated)?
+            .scale_to_exponent(expo_orig)
+            .ok_or(OracleError::Overflow)?;
 
-        return Some(Price {
+        return Ok(Price {
             price:        price_premium.price,
             conf:         conf_orig,
             expo:         price_premium.expo,
@@ -391,6 +525,233 @@ impl Price {
         Some(res)
     }
 
+    /// Get the price of a basket of currencies, estimating its uncertainty with the 2-norm.
+    ///
+    /// This is the same as `price_basket`, except that the confidence of the result is the square
+    /// root of the sum of the squared confidences of the entries (rounded down), rather than their
+    /// sum. This is a tighter estimate for independent errors, e.g., for risk models, whereas
+    /// `price_basket` is conservative. Returns `None` if `amounts` is empty or on overflow.
+    pub fn price_basket_l2(amounts: &[(Price, i64, i32)], result_expo: i32) -> Option<Price> {
+        if amounts.is_empty() {
+            return None;
+        }
+
+        let mut price: i64 = 0;
+        let mut sum_squares: u128 = 0;
+        let mut publish_time = amounts[0].0.publish_time;
+        for amount in amounts {
+            let value = amount
+                .0
+                .cmul(amount.1, amount.2)?
+                .scale_to_exponent(result_expo)?;
+            price = price.checked_add(value.price)?;
+            sum_squares = sum_squares.checked_add((value.conf as u128) * (value.conf as u128))?;
+            publish_time = publish_time.min(value.publish_time);
+        }
+
+        Some(Price {
+            price,
+            conf: u128_isqrt(sum_squares) as u64,
+            expo: result_expo,
+            publish_time,
+        })
+    }
+
+    /// Get the volume-weighted average price of a sequence of fills.
+    ///
+    /// Each entry in `fills` is of the form `(price, qty)`, where `qty` is signed: positive for
+    /// buys and negative for sells. The result is `sum(price * qty) / sum(qty)` with exponent
+    /// `result_expo`, i.e., the average entry price of the resulting net position.
+    ///
+    /// Returns `None` if `fills` is empty, if the net quantity is zero, or if any intermediate
+    /// value cannot be represented.
+    ///
+    /// Example:
+    /// ```ignore
+    /// // Buy 10 units at 100.00 and sell 4 units at 110.00.
+    /// let entry_price: Option<Price> = Price::vwap(&[
+    ///     (Price { price: 10000, conf: 10, expo: -2, publish_time: 100 }, 10),
+    ///     (Price { price: 11000, conf: 10, expo: -2, publish_time: 200 }, -4),
+    ///   ], -2);
+    /// ```
+    pub fn vwap(fills: &[(Price, i64)], result_expo: i32) -> Option<Price> {
+        if fills.is_empty() {
+            return None;
+        }
+
+        let mut notional = Price {
+            price:        0,
+            conf:         0,
+            expo:         result_expo,
+            publish_time: fills[0].0.publish_time,
+        };
+        let mut net_qty: i64 = 0;
+        for (price, qty) in fills {
+            notional = notional.add(&price.cmul(*qty, 0)?.scale_to_exponent(result_expo)?)?;
+            net_qty = net_qty.checked_add(*qty)?;
+        }
+
+        notional
+            .div(&Price {
+                price:        net_qty,
+                conf:         0,
+                expo:         0,
+                publish_time: notional.publish_time,
+            })?
+            .scale_to_exponent(result_expo)
+    }
+
+    /// Get the time-weighted average price of a series of `(time, price)` samples, with exponent
+    /// `result_expo`.
+    ///
+    /// The samples must be sorted by time. Each interval between two consecutive samples is
+    /// weighted by its duration, using the average of the prices at both ends (trapezoidal rule).
+    /// The confidence is averaged in the same way, and the publish time of the result is the
+    /// minimum publish time of the samples.
+    ///
+    /// Returns `None` if there are fewer than two samples, if the samples are not sorted by time
+    /// or span no time at all, or if any value cannot be represented.
+    pub fn twap_samples(samples: &[(UnixTimestamp, Price)], result_expo: i32) -> Option<Price> {
+        if samples.len() < 2 {
+            return None;
+        }
+
+        let mut prices = Vec::with_capacity(samples.len());
+        for (time, price) in samples {
+            prices.push((*time, price.scale_to_exponent(result_expo)?));
+        }
+
+        // Twice the area under the price and confidence curves.
+        let mut price_area: i128 = 0;
+        let mut conf_area: u128 = 0;
+        for window in prices.windows(2) {
+            let (start_time, start) = window[0];
+            let (end_time, end) = window[1];
+
+            let duration = end_time.checked_sub(start_time)?;
+            if duration < 0 {
+                return None;
+            }
+
+            price_area = price_area.checked_add(
+                (start.price as i128 + end.price as i128).checked_mul(duration as i128)?,
+            )?;
+            conf_area = conf_area.checked_add(
+                (start.conf as u128 + end.conf as u128).checked_mul(duration as u128)?,
+            )?;
+        }
+
+        let total_duration = prices[prices.len() - 1].0.checked_sub(prices[0].0)?;
+        if total_duration <= 0 {
+            return None;
+        }
+
+        Some(Price {
+            price:        i64::try_from(price_area.checked_div(2 * total_duration as i128)?)
+                .ok()?,
+            conf:         u64::try_from(conf_area.checked_div(2 * total_duration as u128)?).ok()?,
+            expo:         result_expo,
+            publish_time: prices.iter().map(|(_, price)| price.publish_time).min()?,
+        })
+    }
+
+    /// Get the mid price of a `bid` and an `ask` price, i.e., `(bid + ask) / 2`, with exponent
+    /// `result_expo`.
+    ///
+    /// The confidence of the result covers the half-spread in addition to the average confidence
+    /// of the inputs: `(|ask - bid| + bid.conf + ask.conf) / 2`. This method returns `None` if
+    /// either input or the result cannot be represented with the requested exponent.
+    pub fn midrate(bid: &Price, ask: &Price, result_expo: i32) -> Option<Price> {
+        let bid = bid.scale_to_exponent(result_expo)?;
+        let ask = ask.scale_to_exponent(result_expo)?;
+
+        let price = bid.price.checked_add(ask.price)? / 2;
+        let spread = ask.price.checked_sub(bid.price)?.unsigned_abs();
+        let conf = spread.checked_add(bid.conf)?.checked_add(ask.conf)? / 2;
+
+        Some(Price {
+            price,
+            conf,
+            expo: result_expo,
+            publish_time: bid.publish_time.min(ask.publish_time),
+        })
+    }
+
+    /// Get the geometric mean of two prices, i.e., `sqrt(a * b)`, with exponent `result_expo`.
+    ///
+    /// The exponent of the product is the sum of the exponents of `a` and `b`, which is halved by
+    /// the square root. The confidence is conservative: it covers the square roots of the products
+    /// of both the lower and the upper ends of the confidence intervals of `a` and `b`, with the
+    /// upper end rounded up. This method returns `None` if either price is negative, or if the
+    /// result cannot be represented with the requested exponent.
+    pub fn geometric_mean(a: &Price, b: &Price, result_expo: i32) -> Option<Price> {
+        if a.price < 0 || b.price < 0 {
+            return None;
+        }
+
+        // sqrt(x * 10^(a.expo + b.expo)) = sqrt(x * 10^delta) * 10^result_expo
+        let delta = (a.expo as i64) + (b.expo as i64) - 2 * (result_expo as i64);
+        let scaled_sqrt = |x: u128, round_up: bool| -> Option<u128> {
+            let x = if delta >= 0 {
+                x.checked_mul(10u128.checked_pow(u32::try_from(delta).ok()?)?)?
+            } else {
+                u32::try_from(-delta)
+                    .ok()
+                    .and_then(|d| 10u128.checked_pow(d))
+                    .map_or(0, |divisor| x / divisor)
+            };
+            let root = u128_isqrt(x);
+            Some(if round_up && root * root < x {
+                root + 1
+            } else {
+                root
+            })
+        };
+
+        let (a_price, b_price) = (a.price as u128, b.price as u128);
+        let (a_conf, b_conf) = (a.conf as u128, b.conf as u128);
+
+        let mid = scaled_sqrt(a_price.checked_mul(b_price)?, false)?;
+        let upper = scaled_sqrt((a_price + a_conf).checked_mul(b_price + b_conf)?, true)?;
+        let lower = scaled_sqrt(
+            a_price
+                .saturating_sub(a_conf)
+                .checked_mul(b_price.saturating_sub(b_conf))?,
+            false,
+        )?;
+
+        Some(Price {
+            price:        i64::try_from(mid).ok()?,
+            conf:         u64::try_from((upper - mid).max(mid - lower)).ok()?,
+            expo:         result_expo,
+            publish_time: a.publish_time.min(b.publish_time),
+        })
+    }
+
+    /// Get the combined confidence of a series of prices, e.g., the last few reads of a feed, with
+    /// exponent `result_expo`.
+    ///
+    /// The combined confidence is the root mean square of the confidences, i.e.,
+    /// `sqrt((conf_1^2 + ... + conf_n^2) / n)`, rounded down. This method returns `None` if
+    /// `prices` is empty, if a confidence cannot be represented with the requested exponent, or if
+    /// the sum of squares overflows.
+    pub fn combined_confidence(prices: &[Price], result_expo: i32) -> Option<u64> {
+        if prices.is_empty() {
+            return None;
+        }
+
+        let mut sum_squares: u128 = 0;
+        for price in prices {
+            let conf = Price { price: 0, ..*price }
+                .scale_to_exponent(result_expo)?
+                .conf as u128;
+            sum_squares = sum_squares.checked_add(conf * conf)?;
+        }
+
+        let mean_square = sum_squares / prices.len() as u128;
+        Some(u128_isqrt(mean_square) as u64)
+    }
+
     /// Divide this price by `other` while propagating the uncertainty in both prices into the
     /// result.
     ///
@@ -401,15 +762,25 @@ impl Price {
     /// exponent. If the result is used in a context that requires a specific exponent,
     /// please call `scale_to_exponent` on it.
     pub fn div(&self, other: &Price) -> Option<Price> {
+        self.try_div(other).ok()
+    }
+
+    /// Same as `div`, but returns an `OracleError` describing why the division failed:
+    /// `DivByZero` if `other` is zero, `ConfidenceTooWide` if the confidence of the result is too
+    /// large to be represented, and `Overflow` otherwise.
+    pub fn try_div(&self, other: &Price) -> Result<Price, OracleError> {
         // Price is not guaranteed to store its price/confidence in normalized form.
         // Normalize them here to bound the range of price/conf, which is required to perform
         // arithmetic operations.
+        let base = self.normalize().ok_or(OracleError::Overflow)?;
+        let other = other.normalize().ok_or(OracleError::Overflow)?;
+        Price::div_normalized(&base, &other)
+    }
 
-        let base = self.normalize()?;
-        let other = other.normalize()?;
-
+    /// Divide `base` by `other`, both of which must be normalized (see `normalize`).
+    fn div_normalized(base: &Price, other: &Price) -> Result<Price, OracleError> {
         if other.price == 0 {
-            return None;
+            return Err(OracleError::DivByZero);
         }
 
         // These use at most 27 bits each
@@ -418,8 +789,15 @@ impl Price {
 
         // Compute the midprice, base in terms of other.
         // Uses at most 57 bits
-        let midprice = base_price.checked_mul(PD_SCALE)?.checked_div(other_price)?;
-        let midprice_expo = base.expo.checked_sub(other.expo)?.checked_add(PD_EXPO)?;
+        let midprice = base_price
+            .checked_mul(PD_SCALE)
+            .and_then(|x| x.checked_div(other_price))
+            .ok_or(OracleError::Overflow)?;
+        let midprice_expo = base
+            .expo
+            .checked_sub(other.expo)
+            .and_then(|expo| expo.checked_add(PD_EXPO))
+            .ok_or(OracleError::Overflow)?;
 
         // Compute the confidence interval.
         // This code uses the 1-norm instead of the 2-norm for computational reasons.
@@ -432,46 +810,93 @@ impl Price {
         // shouldn't matter considering that confidence intervals are typically ~0.1% of the price.
 
         // This uses 57 bits and has an exponent of PD_EXPO.
-        let other_confidence_pct: u64 =
-            other.conf.checked_mul(PD_SCALE)?.checked_div(other_price)?;
+        let other_confidence_pct: u64 = other
+            .conf
+            .checked_mul(PD_SCALE)
+            .and_then(|x| x.checked_div(other_price))
+            .ok_or(OracleError::Overflow)?;
 
         // first term is 57 bits, second term is 57 + 58 - 29 = 86 bits. Same exponent as the
-        // midprice. Note: the computation of the 2nd term consumes about 3k ops. We may
-        // want to optimize this.
-        let conf = (base.conf.checked_mul(PD_SCALE)?.checked_div(other_price)? as u128)
-            .checked_add(
-                (other_confidence_pct as u128)
-                    .checked_mul(midprice as u128)?
-                    .checked_div(PD_SCALE as u128)?,
-            )?;
+        // midprice. The u128 arithmetic for the 2nd term consumes about 3k ops, so it is only
+        // used when the product doesn't fit in a u64, which is rare for typical confidence
+        // intervals. Both paths compute the same (truncated) value.
+        let first_term = base
+            .conf
+            .checked_mul(PD_SCALE)
+            .and_then(|x| x.checked_div(other_price))
+            .ok_or(OracleError::Overflow)?;
+        let second_term = match other_confidence_pct.checked_mul(midprice) {
+            Some(product) => (product / PD_SCALE) as u128,
+            None => (other_confidence_pct as u128)
+                .checked_mul(midprice as u128)
+                .and_then(|x| x.checked_div(PD_SCALE as u128))
+                .ok_or(OracleError::Overflow)?,
+        };
+        let conf = (first_term as u128)
+            .checked_add(second_term)
+            .ok_or(OracleError::Overflow)?;
 
         // Note that this check only fails if an argument's confidence interval was >> its price,
-        // in which case None is a reasonable result, as we have essentially 0 information about the
-        // price.
-        if conf < (u64::MAX as u128) {
-            Some(Price {
-                price:        (midprice as i64)
-                    .checked_mul(base_sign)?
-                    .checked_mul(other_sign)?,
-                conf:         conf as u64,
-                expo:         midprice_expo,
-                publish_time: self.publish_time.min(other.publish_time),
-            })
-        } else {
-            None
+        // in which case an error is a reasonable result, as we have essentially 0 information
+        // about the price.
+        if conf >= (u64::MAX as u128) {
+            return Err(OracleError::ConfidenceTooWide);
         }
+
+        Ok(Price {
+            price:        (midprice as i64)
+                .checked_mul(base_sign)
+                .and_then(|price| price.checked_mul(other_sign))
+                .ok_or(OracleError::Overflow)?,
+            conf:         conf as u64,
+            expo:         midprice_expo,
+            publish_time: base.publish_time.min(other.publish_time),
+        })
     }
 
     /// Add `other` to this, propagating uncertainty in both prices.
     ///
     /// Requires both `Price`s to have the same exponent -- use `scale_to_exponent` on
-    /// the arguments if necessary.
+    /// the arguments if necessary. Returns `None` if the exponents differ.
     ///
     /// TODO: could generalize this method to support different exponents.
     pub fn add(&self, other: &Price) -> Option<Price> {
+        self.try_add(other).ok()
+    }
+
+    /// Same as `add`, but returns an error instead of `None`: `OracleError::ExponentMismatch` if
+    /// the exponents differ and `OracleError::Overflow` if the price or confidence of the sum
+    /// overflows.
+    pub fn try_add(&self, other: &Price) -> Result<Price, OracleError> {
+        if self.expo != other.expo {
+            return Err(OracleError::ExponentMismatch);
+        }
+
+        let price = self
+            .price
+            .checked_add(other.price)
+            .ok_or(OracleError::Overflow)?;
+        // The conf should technically be sqrt(a^2 + b^2), but that's harder to compute.
+        let conf = self
+            .conf
+            .checked_add(other.conf)
+            .ok_or(OracleError::Overflow)?;
+        Ok(Price {
+            price,
+            conf,
+            expo: self.expo,
+            publish_time: self.publish_time.min(other.publish_time),
+        })
+    }
+
+    /// Subtract `other` from this, propagating uncertainty in both prices.
+    ///
+    /// Requires both `Price`s to have the same exponent -- use `scale_to_exponent` on
+    /// the arguments if necessary.
+    pub fn sub(&self, other: &Price) -> Option<Price> {
         assert_eq!(self.expo, other.expo);
 
-        let price = self.price.checked_add(other.price)?;
+        let price = self.price.checked_sub(other.price)?;
         // The conf should technically be sqrt(a^2 + b^2), but that's harder to compute.
         let conf = self.conf.checked_add(other.conf)?;
         Some(Price {
@@ -492,14 +917,68 @@ impl Price {
         })
     }
 
+    /// Get this price including a proportional fee of `fee_bps` basis points, i.e.,
+    /// `price * (1 + fee_bps / 10000)`, with exponent `result_expo`.
+    ///
+    /// The fee is signed: use a positive fee for an ask price and a negative fee for a bid
+    /// price. The confidence is scaled by the same factor as the price. Returns `None` if the fee
+    /// is below -100% or if the result cannot be represented with the requested exponent.
+    pub fn with_fee_bps(&self, fee_bps: i64, result_expo: i32) -> Option<Price> {
+        let factor = fee_bps.checked_add(10_000)?;
+        if factor < 0 {
+            return None;
+        }
+
+        self.cmul(factor, -4)?.scale_to_exponent(result_expo)
+    }
+
+    /// Get the value of `amount * 10^amount_expo` units of the asset at this price, with exponent
+    /// `result_expo`.
+    ///
+    /// This is useful for valuing raw token quantities, e.g., `amount_expo` is `-decimals` of a
+    /// token mint. The confidence is scaled by the same quantity as the price. Returns `None` if
+    /// `amount` exceeds `i64::MAX` or if the result cannot be represented with the requested
+    /// exponent.
+    pub fn get_quantity_value(
+        &self,
+        amount: u64,
+        amount_expo: i32,
+        result_expo: i32,
+    ) -> Option<Price> {
+        let amount = i64::try_from(amount).ok()?;
+        self.cmul(amount, amount_expo)?
+            .scale_to_exponent(result_expo)
+    }
+
+    /// Get the amount of the asset worth `value` at this price, as a raw quantity with exponent
+    /// `amount_expo`, i.e., the result `a` satisfies `a * 10^amount_expo * price ~= value`.
+    ///
+    /// This is the inverse of `get_quantity_value`. The confidence of the result is discarded.
+    /// Returns `None` if this price is 0, if the amount is negative, or if it cannot be
+    /// represented with the requested exponent.
+    pub fn get_amount_for_value(&self, value: Price, amount_expo: i32) -> Option<u64> {
+        let amount = value.div(self)?.scale_to_exponent(amount_expo)?;
+        u64::try_from(amount.price).ok()
+    }
+
     /// Multiply this `Price` by `other`, propagating any uncertainty.
     pub fn mul(&self, other: &Price) -> Option<Price> {
+        self.try_mul(other).ok()
+    }
+
+    /// Same as `mul`, but returns `OracleError::Overflow` instead of `None` if the product
+    /// cannot be represented.
+    pub fn try_mul(&self, other: &Price) -> Result<Price, OracleError> {
         // Price is not guaranteed to store its price/confidence in normalized form.
         // Normalize them here to bound the range of price/conf, which is required to perform
         // arithmetic operations.
-        let base = self.normalize()?;
-        let other = other.normalize()?;
+        let base = self.normalize().ok_or(OracleError::Overflow)?;
+        let other = other.normalize().ok_or(OracleError::Overflow)?;
+        Price::mul_normalized(&base, &other).ok_or(OracleError::Overflow)
+    }
 
+    /// Multiply `base` by `other`, both of which must be normalized (see `normalize`).
+    fn mul_normalized(base: &Price, other: &Price) -> Option<Price> {
         // These use at most 27 bits each
         let (base_price, base_sign) = Price::to_unsigned(base.price);
         let (other_price, other_sign) = Price::to_unsigned(other.price);
@@ -523,7 +1002,7 @@ impl Price {
                 .checked_mul(other_sign)?,
             conf,
             expo: midprice_expo,
-            publish_time: self.publish_time.min(other.publish_time),
+            publish_time: base.publish_time.min(other.publish_time),
         })
     }
 
@@ -533,18 +1012,26 @@ impl Price {
         // signed division is very expensive in op count
         let (mut p, s) = Price::to_unsigned(self.price);
         let mut c = self.conf;
-        let mut e = self.expo;
 
-        while p > MAX_PD_V_U64 || c > MAX_PD_V_U64 {
-            p = p.checked_div(10)?;
-            c = c.checked_div(10)?;
-            e = e.checked_add(1)?;
+        // Compute the number of decimal digits to drop directly rather than dividing by 10 in a
+        // loop, which costs ops proportional to the magnitude of the inputs. Dividing by 10^k once
+        // truncates exactly like dividing by 10 k times.
+        let m = p.max(c);
+        let mut k = 0;
+        if m > MAX_PD_V_U64 {
+            k = m.ilog10() - MAX_PD_V_U64.ilog10();
+            if m / 10u64.checked_pow(k)? > MAX_PD_V_U64 {
+                k += 1;
+            }
+            let factor = 10u64.checked_pow(k)?;
+            p = p.checked_div(factor)?;
+            c = c.checked_div(factor)?;
         }
 
         Some(Price {
             price:        (p as i64).checked_mul(s)?,
             conf:         c,
-            expo:         e,
+            expo:         self.expo.checked_add(k as i32)?,
             publish_time: self.publish_time,
         })
     }
@@ -558,6 +1045,32 @@ impl Price {
     /// function will return 0 +- 0.
     pub fn scale_to_exponent(&self, target_expo: i32) -> Option<Price> {
         let mut delta = target_expo.checked_sub(self.expo)?;
+
+        // Fast path: scale by 10^|delta| in a single operation. Repeated truncating division by 10
+        // is equivalent to a single division by 10^|delta|, and a single multiplication overflows
+        // exactly when the stepwise one would. If 10^|delta| doesn't fit in an i64, fall back to
+        // the stepwise loops below, which short-circuit quickly for such deltas.
+        if let Some(factor) = 10i64.checked_pow(delta.unsigned_abs()) {
+            let (p, c) = if delta >= 0 {
+                (
+                    self.price.checked_div(factor)?,
+                    self.conf.checked_div(factor as u64)?,
+                )
+            } else {
+                (
+                    self.price.checked_mul(factor)?,
+                    self.conf.checked_mul(factor as u64)?,
+                )
+            };
+
+            return Some(Price {
+                price:        p,
+                conf:         c,
+                expo:         target_expo,
+                publish_time: self.publish_time,
+            });
+        }
+
         if delta >= 0 {
             let mut p = self.price;
             let mut c = self.conf;
@@ -594,6 +1107,96 @@ impl Price {
         }
     }
 
+    /// Get the range of the `price` field of prices with exponent `expo`, i.e., the smallest and
+    /// largest representable values are `min * 10^expo` and `max * 10^expo`.
+    ///
+    /// The range of the fixed-point representation does not depend on the exponent, so this is
+    /// always `(i64::MIN, i64::MAX)`; use `fits_in_exponent` to check whether a specific price can
+    /// be scaled to `expo`.
+    pub fn representable_range(_expo: i32) -> (i64, i64) {
+        (i64::MIN, i64::MAX)
+    }
+
+    /// Check whether `scale_to_exponent(expo)` would succeed, without scaling this price.
+    pub fn fits_in_exponent(&self, expo: i32) -> bool {
+        let delta = match expo.checked_sub(self.expo) {
+            Some(delta) => delta,
+            None => return false,
+        };
+        // Scaling to a larger exponent only drops digits.
+        if delta >= 0 {
+            return true;
+        }
+
+        match 10u64.checked_pow(delta.unsigned_abs()) {
+            Some(factor) => {
+                self.conf.checked_mul(factor).is_some()
+                    && (self.price == 0
+                        || i64::try_from(factor)
+                            .ok()
+                            .and_then(|factor| self.price.checked_mul(factor))
+                            .is_some())
+            }
+            None => self.price == 0 && self.conf == 0,
+        }
+    }
+
+    /// Round this price to `figures` significant digits, increasing the exponent accordingly.
+    ///
+    /// The price is rounded to the nearest value (halves away from zero), and the confidence is
+    /// scaled by the same factor and rounded up, so the interval is never narrowed. For example,
+    /// `123456 * 10^-3` rounded to 3 significant figures is `123 * 10^0`. Prices that already have
+    /// at most `figures` significant digits are returned unchanged. Returns `None` if `figures` is
+    /// 0 or if the resulting exponent overflows.
+    pub fn round_to_significant_figures(&self, figures: u32) -> Option<Price> {
+        if figures == 0 {
+            return None;
+        }
+
+        let magnitude = self.price.unsigned_abs();
+        let digits = magnitude.checked_ilog10().map_or(1, |log| log + 1);
+        if digits <= figures {
+            return Some(*self);
+        }
+
+        let mut drop = digits - figures;
+        let factor = 10u64.pow(drop);
+        let mut rounded = magnitude / factor;
+        if (magnitude % factor) as u128 * 2 >= factor as u128 {
+            rounded += 1;
+        }
+        let mut conf = self.conf / factor + u64::from(self.conf % factor != 0);
+
+        // Rounding up may carry into an additional digit, e.g., 999 to 2 figures is 100 * 10^1.
+        if rounded == 10u64.pow(figures) {
+            rounded /= 10;
+            conf = conf / 10 + u64::from(conf % 10 != 0);
+            drop += 1;
+        }
+
+        Some(Price {
+            price: (rounded as i64).checked_mul(self.price.signum())?,
+            conf,
+            expo: self.expo.checked_add(i32::try_from(drop).ok()?)?,
+            publish_time: self.publish_time,
+        })
+    }
+
+    /// Scale `a` and `b` in place to a common exponent, the smaller of their two exponents, so
+    /// their `price` and `conf` fields can be compared or combined directly.
+    ///
+    /// Returns `None` and leaves both prices unchanged if either of them cannot be represented with
+    /// the common exponent.
+    pub fn normalize_pair(a: &mut Price, b: &mut Price) -> Option<()> {
+        let expo = a.expo.min(b.expo);
+        let scaled_a = a.scale_to_exponent(expo)?;
+        let scaled_b = b.scale_to_exponent(expo)?;
+
+        *a = scaled_a;
+        *b = scaled_b;
+        Some(())
+    }
+
     /// Helper function to convert signed integers to unsigned and a sign bit, which simplifies
     /// some of the computations above.
     fn to_unsigned(x: i64) -> (u64, i64) {
@@ -639,47 +1242,423 @@ impl Price {
 
         return Some(frac);
     }
-}
-
-#[cfg(test)]
-mod test {
-    use quickcheck::TestResult;
-    use quickcheck_macros::quickcheck;
-    use std::convert::TryFrom;
 
-    use crate::price::{
-        Price,
-        MAX_PD_V_U64,
-        PD_EXPO,
-        PD_SCALE,
-    };
+    /// Get the absolute value of this price. The confidence, exponent and publish time are
+    /// preserved.
+    ///
+    /// Returns `None` if the price is `i64::MIN`, whose absolute value cannot be represented.
+    pub fn abs(&self) -> Option<Price> {
+        Some(Price {
+            price: self.price.checked_abs()?,
+            ..*self
+        })
+    }
 
-    const MAX_PD_V_I64: i64 = MAX_PD_V_U64 as i64;
-    const MIN_PD_V_I64: i64 = -MAX_PD_V_I64;
+    /// Compare the real values of this price and `other`, taking their exponents into account.
+    /// The confidence intervals and publish times are ignored.
+    ///
+    /// Both prices are scaled to the smaller of the two exponents before comparing them, so
+    /// `12345 * 10^-2` and `123450 * 10^-3` compare `Equal`. Returns `None` if the rescaled price
+    /// cannot be represented. This is convenient for sorting, e.g.,
+    /// `prices.sort_by(|a, b| a.cmp_value(b).unwrap_or(Ordering::Equal))`.
+    pub fn cmp_value(&self, other: &Price) -> Option<std::cmp::Ordering> {
+        let expo = self.expo.min(other.expo);
+        let base = self.scale_to_exponent(expo)?;
+        let other = other.scale_to_exponent(expo)?;
+        Some(base.price.cmp(&other.price))
+    }
 
-    fn pc(price: i64, conf: u64, expo: i32) -> Price {
-        Price {
-            price,
-            conf,
-            expo,
-            publish_time: 0,
+    /// Get whichever of this price and `other` has the smaller real value, e.g., to value
+    /// collateral conservatively when combining several sources.
+    ///
+    /// The values are compared with `cmp_value`, and the chosen `Price` is returned unchanged,
+    /// with its original exponent. If both values are equal, this price is returned. Returns
+    /// `None` if the prices cannot be rescaled to a common exponent.
+    pub fn min_value(&self, other: &Price) -> Option<Price> {
+        match self.cmp_value(other)? {
+            std::cmp::Ordering::Greater => Some(*other),
+            _ => Some(*self),
         }
     }
 
-    fn pc_scaled(price: i64, conf: u64, cur_expo: i32, expo: i32) -> Price {
-        Price {
-            price,
-            conf,
-            expo: cur_expo,
-            publish_time: 0,
+    /// Get whichever of this price and `other` has the larger real value, e.g., to value debt
+    /// conservatively when combining several sources. See `min_value`.
+    pub fn max_value(&self, other: &Price) -> Option<Price> {
+        match self.cmp_value(other)? {
+            std::cmp::Ordering::Less => Some(*other),
+            _ => Some(*self),
         }
-        .scale_to_exponent(expo)
-        .unwrap()
     }
 
+    /// Check whether the confidence intervals `[price - conf, price + conf]` of this price and
+    /// `other` intersect, e.g., to decide whether two feeds are statistically distinguishable.
+    ///
+    /// Both prices are scaled to the smaller of the two exponents first, as in `cmp_value`. The
+    /// intervals are closed, so intervals that only share an endpoint overlap. Returns `None` if
+    /// the rescaled price or confidence cannot be represented.
+    pub fn confidence_intervals_overlap(&self, other: &Price) -> Option<bool> {
+        let expo = self.expo.min(other.expo);
+        let base = self.scale_to_exponent(expo)?;
+        let other = other.scale_to_exponent(expo)?;
+
+        // The bounds can exceed the range of an i64, but not of an i128
+        let lower = |price: &Price| price.price as i128 - price.conf as i128;
+        let upper = |price: &Price| price.price as i128 + price.conf as i128;
+
+        Some(lower(&base) <= upper(&other) && lower(&other) <= upper(&base))
+    }
 
-    #[test]
-    fn test_normalize() {
+    /// Get the inverse-variance weight of this price, i.e., `1 / conf^2` scaled by `10^36` and
+    /// rounded down, for use by external aggregators.
+    ///
+    /// The weight treats the confidence interval as a standard deviation and ignores the
+    /// exponent, so only weights of prices with the same exponent are comparable. Returns `None`
+    /// if the confidence is zero.
+    pub fn inverse_variance_weight(&self) -> Option<u128> {
+        let variance = (self.conf as u128).checked_mul(self.conf as u128)?;
+        INVERSE_VARIANCE_SCALE.checked_div(variance)
+    }
+
+    /// Check whether the confidence interval of this price includes zero, i.e., whether
+    /// `conf >= |price|`.
+    ///
+    /// Such a price carries essentially no information about its sign, and many protocols must
+    /// reject it.
+    pub fn band_crosses_zero(&self) -> bool {
+        self.conf >= self.price.unsigned_abs()
+    }
+
+    /// Construct a `Price` with exponent `expo` from floating-point `value` and `conf`, e.g. a
+    /// reference price from an external source, so it can be combined with Pyth prices.
+    ///
+    /// Both numbers are multiplied by `10^-expo` and rounded to the nearest integer. Returns
+    /// `None` if either number is NaN or infinite, if `conf` is negative, or if the result does not
+    /// fit in the `price` (`i64`) or `conf` (`u64`) fields.
+    ///
+    /// `Price::from_f64(x, c, e, t)?.to_f64()` round-trips to `x` within half a unit of the
+    /// exponent, i.e., `0.5 * 10^e`, plus the relative error inherent to `f64` arithmetic.
+    pub fn from_f64(
+        value: f64,
+        conf: f64,
+        expo: i32,
+        publish_time: UnixTimestamp,
+    ) -> Option<Price> {
+        // 2^63 and 2^64 are exactly representable, unlike i64::MAX and u64::MAX.
+        const I64_BOUND: f64 = 9_223_372_036_854_775_808.0;
+        const U64_BOUND: f64 = 18_446_744_073_709_551_616.0;
+
+        if !value.is_finite() || !conf.is_finite() || conf < 0.0 {
+            return None;
+        }
+
+        let price = Price::scale_f64(value, expo.saturating_neg()).round();
+        let conf = Price::scale_f64(conf, expo.saturating_neg()).round();
+        if !(-I64_BOUND..I64_BOUND).contains(&price) || conf >= U64_BOUND {
+            return None;
+        }
+
+        Some(Price {
+            price: price as i64,
+            conf: conf as u64,
+            expo,
+            publish_time,
+        })
+    }
+
+    /// Check whether this price was published more than `age` seconds away from `current_time`,
+    /// i.e., whether `PriceFeed::get_price_no_older_than` would reject it.
+    ///
+    /// A price published exactly `age` seconds before `current_time` is still fresh.
+    pub fn is_older_than(&self, current_time: UnixTimestamp, age: DurationInSeconds) -> bool {
+        self.publish_time.abs_diff(current_time) > age
+    }
+
+    /// Get the price as a floating-point number, i.e., `price * 10^expo`.
+    ///
+    /// This is intended for display and analytics only: `f64` cannot represent every value exactly,
+    /// so the result may lose precision and should not be used for on-chain computations.
+    pub fn to_f64(&self) -> f64 {
+        Price::scale_f64(self.price as f64, self.expo)
+    }
+
+    /// Get the confidence interval as a floating-point number, i.e., `conf * 10^expo`.
+    ///
+    /// Like `to_f64`, this is intended for display and analytics only.
+    pub fn conf_to_f64(&self) -> f64 {
+        Price::scale_f64(self.conf as f64, self.expo)
+    }
+
+    /// Get the ratio of the confidence interval to the magnitude of the price, i.e.,
+    /// `conf / |price|`, as a floating-point number.
+    ///
+    /// The exponent cancels out, so the ratio is the same for any exponent. Returns `None` if the
+    /// price is 0. Like `to_f64`, the result may lose precision, so it is intended for threshold
+    /// checks rather than exact computations.
+    pub fn confidence_ratio(&self) -> Option<f64> {
+        if self.price == 0 {
+            return None;
+        }
+
+        Some(self.conf as f64 / self.price.unsigned_abs() as f64)
+    }
+
+    /// Get the number of decimals needed to display both the price and the confidence of this
+    /// `Price` exactly, so they can be formatted consistently.
+    ///
+    /// Trailing zeros are not counted, e.g., `12300 +- 250` with exponent -3 is displayed as
+    /// `12.30 +- 0.25` and needs 2 decimals. Non-negative exponents need no decimals.
+    pub fn display_decimals(&self) -> usize {
+        Price::significant_decimals(self.price.unsigned_abs(), self.expo)
+            .max(Price::significant_decimals(self.conf, self.expo))
+    }
+
+    /// Helper function to get the number of decimals needed to display `value * 10^expo` exactly.
+    fn significant_decimals(mut value: u64, expo: i32) -> usize {
+        if expo >= 0 || value == 0 {
+            return 0;
+        }
+
+        let mut decimals = expo.unsigned_abs() as usize;
+        while decimals > 0 && value % 10 == 0 {
+            value /= 10;
+            decimals -= 1;
+        }
+        decimals
+    }
+
+    /// Write `value * 10^expo` as a decimal number, or in scientific notation (e.g. `1e40`) if
+    /// `|expo|` is larger than `MAX_DISPLAY_DECIMAL_EXPO`.
+    fn fmt_decimal(f: &mut std::fmt::Formatter<'_>, value: u64, expo: i32) -> std::fmt::Result {
+        if expo.unsigned_abs() > MAX_DISPLAY_DECIMAL_EXPO {
+            return write!(f, "{}e{}", value, expo);
+        }
+
+        let digits = value.to_string();
+        if expo >= 0 {
+            return write!(f, "{}{:0<width$}", digits, "", width = expo as usize);
+        }
+
+        let decimals = expo.unsigned_abs() as usize;
+        if digits.len() > decimals {
+            let (integer, fraction) = digits.split_at(digits.len() - decimals);
+            write!(f, "{}.{}", integer, fraction)
+        } else {
+            write!(
+                f,
+                "0.{:0<width$}{}",
+                "",
+                digits,
+                width = decimals - digits.len()
+            )
+        }
+    }
+
+    /// Helper function to compute `value * 10^expo`. Negative exponents divide by a power of 10,
+    /// which is more accurate than multiplying by its (inexact) reciprocal.
+    fn scale_f64(value: f64, expo: i32) -> f64 {
+        if expo < 0 {
+            value / 10f64.powi(expo.saturating_neg())
+        } else {
+            value * 10f64.powi(expo)
+        }
+    }
+}
+
+/// Formats the price and confidence as decimal numbers, e.g., `123.45 ± 2.67` for
+/// `Price { price: 12345, conf: 267, expo: -2, .. }`. Prices with an exponent larger than 32 in
+/// absolute value are formatted in scientific notation instead, e.g., `123e40 ± 1e40`.
+impl std::fmt::Display for Price {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.price < 0 {
+            write!(f, "-")?;
+        }
+        Price::fmt_decimal(f, self.price.unsigned_abs(), self.expo)?;
+        write!(f, " ± ")?;
+        Price::fmt_decimal(f, self.conf, self.expo)
+    }
+}
+
+/// A `Price` that has been normalized (see `Price::normalize`) once on construction.
+///
+/// `Price::mul` and `Price::div` normalize both of their arguments on every call. When the same
+/// price is used in a chain of operations, wrapping it in a `NormalizedPrice` skips the repeated
+/// normalization of that operand. The results are identical to the ones of the `Price` methods.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NormalizedPrice(Price);
+
+impl NormalizedPrice {
+    /// Normalize `price`. Returns `None` if `price` cannot be normalized.
+    pub fn new(price: &Price) -> Option<NormalizedPrice> {
+        Some(NormalizedPrice(price.normalize()?))
+    }
+
+    /// Get the normalized price.
+    pub fn price(&self) -> Price {
+        self.0
+    }
+
+    /// Multiply this price by `other`. See `Price::mul`.
+    pub fn mul(&self, other: &Price) -> Option<Price> {
+        Price::mul_normalized(&self.0, &other.normalize()?)
+    }
+
+    /// Divide this price by `other`. See `Price::div`.
+    pub fn div(&self, other: &Price) -> Option<Price> {
+        Price::div_normalized(&self.0, &other.normalize()?).ok()
+    }
+
+    /// Add `other` to this price. See `Price::add`.
+    ///
+    /// Requires `other` to have the same exponent as the normalized price, i.e.,
+    /// `self.price().expo`.
+    pub fn add(&self, other: &Price) -> Option<Price> {
+        self.0.add(other)
+    }
+}
+
+/// The arithmetic operators on `Price` delegate to the checked methods of the same name, so
+/// expressions such as `(a * b).and_then(|x| x + c)` keep their overflow semantics: the output is
+/// an `Option<Price>` which is `None` whenever the underlying method would return `None`.
+///
+/// Like `Price::add`, `+` and `-` require both operands to have the same exponent. The operators
+/// are also implemented for references, e.g. `&a * &b`, which avoids copies in loops.
+impl std::ops::Add for Price {
+    type Output = Option<Price>;
+
+    fn add(self, other: Price) -> Option<Price> {
+        Price::add(&self, &other)
+    }
+}
+
+impl std::ops::Sub for Price {
+    type Output = Option<Price>;
+
+    fn sub(self, other: Price) -> Option<Price> {
+        Price::sub(&self, &other)
+    }
+}
+
+impl std::ops::Mul for Price {
+    type Output = Option<Price>;
+
+    fn mul(self, other: Price) -> Option<Price> {
+        Price::mul(&self, &other)
+    }
+}
+
+impl std::ops::Div for Price {
+    type Output = Option<Price>;
+
+    fn div(self, other: Price) -> Option<Price> {
+        Price::div(&self, &other)
+    }
+}
+
+impl std::ops::Add for &Price {
+    type Output = Option<Price>;
+
+    fn add(self, other: &Price) -> Option<Price> {
+        Price::add(self, other)
+    }
+}
+
+impl std::ops::Sub for &Price {
+    type Output = Option<Price>;
+
+    fn sub(self, other: &Price) -> Option<Price> {
+        Price::sub(self, other)
+    }
+}
+
+impl std::ops::Mul for &Price {
+    type Output = Option<Price>;
+
+    fn mul(self, other: &Price) -> Option<Price> {
+        Price::mul(self, other)
+    }
+}
+
+impl std::ops::Div for &Price {
+    type Output = Option<Price>;
+
+    fn div(self, other: &Price) -> Option<Price> {
+        Price::div(self, other)
+    }
+}
+
+/// Legacy representation of a price and confidence interval without a publish time, as persisted
+/// by older versions of this SDK.
+#[derive(serde::Deserialize)]
+struct PriceConf {
+    #[serde(with = "utils::as_string")]
+    price: i64,
+    #[serde(with = "utils::as_string")]
+    conf:  u64,
+    expo:  i32,
+}
+
+/// Integer square root of `n`, rounded down, i.e., the largest `r` such that `r * r <= n`.
+///
+/// This uses Newton's method starting from a power of two that is at least `sqrt(n)`, so the
+/// iterates decrease monotonically towards the result and `x + n / x` never overflows.
+pub(crate) fn u128_isqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+
+    let bits = 128 - n.leading_zeros();
+    let mut x = 1u128 << ((bits + 1) / 2);
+    loop {
+        let y = (x + n / x) / 2;
+        if y >= x {
+            return x;
+        }
+        x = y;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use quickcheck::TestResult;
+    use quickcheck_macros::quickcheck;
+    use std::convert::TryFrom;
+
+    use crate::price::{
+        u128_isqrt,
+        NormalizedPrice,
+        Price,
+        MAX_PD_V_U64,
+        PD_EXPO,
+        PD_SCALE,
+    };
+    use crate::OracleError;
+
+    const MAX_PD_V_I64: i64 = MAX_PD_V_U64 as i64;
+    const MIN_PD_V_I64: i64 = -MAX_PD_V_I64;
+
+    fn pc(price: i64, conf: u64, expo: i32) -> Price {
+        Price {
+            price,
+            conf,
+            expo,
+            publish_time: 0,
+        }
+    }
+
+    fn pc_scaled(price: i64, conf: u64, cur_expo: i32, expo: i32) -> Price {
+        Price {
+            price,
+            conf,
+            expo: cur_expo,
+            publish_time: 0,
+        }
+        .scale_to_exponent(expo)
+        .unwrap()
+    }
+
+
+    #[test]
+    fn test_normalize() {
         fn succeeds(price1: Price, expected: Price) {
             assert_eq!(price1.normalize().unwrap(), expected);
         }
@@ -698,6 +1677,16 @@ mod test {
             pc(-2 * (PD_SCALE as i64) / 100, 3 * PD_SCALE / 100, 2),
         );
 
+        // values around MAX_PD_V, where the number of digits alone doesn't determine the shift
+        let max_i64 = MAX_PD_V_U64 as i64;
+        succeeds(pc(max_i64, MAX_PD_V_U64, 0), pc(max_i64, MAX_PD_V_U64, 0));
+        succeeds(pc(max_i64 + 1, 0, 0), pc((max_i64 + 1) / 10, 0, 1));
+        succeeds(pc(999_999_999, 0, 0), pc(99_999_999, 0, 1));
+        succeeds(pc(0, 10 * MAX_PD_V_U64 + 9, 0), pc(0, MAX_PD_V_U64, 1));
+        succeeds(pc(-10 * max_i64 - 9, 0, 0), pc(-max_i64, 0, 1));
+        succeeds(pc(-10 * max_i64 - 10, 0, 0), pc(-(max_i64 + 1) / 10, 0, 2));
+        succeeds(pc(27 * max_i64, 19, -5), pc(27 * max_i64 / 100, 0, -3));
+
         // the i64 / u64 max values are a factor of 10^11 larger than MAX_PD_V
         let expo = -(PD_EXPO - 2);
         let scale_i64 = (PD_SCALE as i64) * 100;
@@ -755,6 +1744,16 @@ mod test {
         fails(pc(1234, 0, 0), -20);
         fails(pc(0, 1234, 0), -20);
 
+        // deltas around the largest power of 10 that fits in an i64
+        succeeds(pc(i64::MAX, u64::MAX, 0), 18, pc(9, 18, 18));
+        succeeds(pc(i64::MIN, u64::MAX, 0), 19, pc(0, 1, 19));
+        succeeds(pc(i64::MIN, u64::MAX, 0), 20, pc(0, 0, 20));
+        succeeds(pc(1, 1, 0), -18, pc(10i64.pow(18), 10u64.pow(18), -18));
+        succeeds(pc(0, 1, 0), -19, pc(0, 10u64.pow(19), -19));
+        fails(pc(1, 1, 0), -19);
+        fails(pc(10, 1, 0), -18);
+        succeeds(pc(-9, 0, 0), -18, pc(-9 * 10i64.pow(18), 0, -18));
+
         // fails because exponent delta overflows
         fails(pc(1, 1, i32::MIN), i32::MAX);
 
@@ -1672,6 +2671,88 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_try_valuation_price_errors() {
+        let price = pc(100, 5, -2);
+
+        // Successful valuations agree with the `Option` methods
+        assert_eq!(
+            price
+                .try_get_collateral_valuation_price(50, 100, 100, 90, -2)
+                .ok(),
+            price.get_collateral_valuation_price(50, 100, 100, 90, -2)
+        );
+        assert!(price
+            .try_get_collateral_valuation_price(50, 100, 100, 90, -2)
+            .is_ok());
+        assert_eq!(
+            price
+                .try_get_borrow_valuation_price(50, 100, 100, 110, -2)
+                .ok(),
+            price.get_borrow_valuation_price(50, 100, 100, 110, -2)
+        );
+        assert!(price
+            .try_get_borrow_valuation_price(50, 100, 100, 110, -2)
+            .is_ok());
+
+        // Invalid discount or premium ordering
+        assert_eq!(
+            price.try_get_collateral_valuation_price(50, 100, 90, 100, -2),
+            Err(OracleError::InitialDiscountExceedsFinalDiscount)
+        );
+        assert_eq!(
+            price.try_get_borrow_valuation_price(50, 100, 110, 100, -2),
+            Err(OracleError::InitialPremiumExceedsFinalPremium)
+        );
+
+        // Arguments larger than i64::MAX
+        let too_large = i64::MAX as u64 + 1;
+        assert_eq!(
+            price.try_get_collateral_valuation_price(too_large, 100, 100, 90, -2),
+            Err(OracleError::I64ConversionError)
+        );
+        assert_eq!(
+            price.try_get_collateral_valuation_price(50, too_large, 100, 90, -2),
+            Err(OracleError::I64ConversionError)
+        );
+        assert_eq!(
+            price.try_get_collateral_valuation_price(50, 100, too_large, 90, -2),
+            Err(OracleError::I64ConversionError)
+        );
+        assert_eq!(
+            price.try_get_borrow_valuation_price(50, 100, 100, too_large, -2),
+            Err(OracleError::I64ConversionError)
+        );
+        assert_eq!(
+            price.try_get_borrow_valuation_price(too_large, 100, 100, 110, -2),
+            Err(OracleError::I64ConversionError)
+        );
+
+        // Zero endpoint
+        assert_eq!(
+            price.try_get_collateral_valuation_price(0, 0, 100, 90, -2),
+            Err(OracleError::ZeroEndpoint)
+        );
+        assert_eq!(
+            price.try_get_borrow_valuation_price(0, 0, 100, 110, -2),
+            Err(OracleError::ZeroEndpoint)
+        );
+
+        // Overflow in the interpolation or the valuation
+        assert_eq!(
+            price.try_get_collateral_valuation_price(50, 100, 100, 90, i32::MIN),
+            Err(OracleError::Overflow)
+        );
+        assert_eq!(
+            pc(i64::MAX, 0, i32::MAX).try_get_borrow_valuation_price(50, 100, 100, 110, -2),
+            Err(OracleError::Overflow)
+        );
+        assert_eq!(
+            pc(i64::MAX, 0, i32::MAX).get_borrow_valuation_price(50, 100, 100, 110, -2),
+            None
+        );
+    }
+
     #[test]
     fn test_affine_combination() {
         fn succeeds(
@@ -2251,4 +3332,1333 @@ mod test {
         // fails due to div by 0
         fails(100, 0);
     }
+
+    #[test]
+    fn test_sub() {
+        fn succeeds(price1: Price, price2: Price, expected: Price) {
+            assert_eq!(price1.sub(&price2).unwrap(), expected);
+        }
+
+        fn fails(price1: Price, price2: Price) {
+            assert_eq!(price1.sub(&price2), None);
+        }
+
+        succeeds(pc(100, 10, -2), pc(30, 5, -2), pc(70, 15, -2));
+        succeeds(pc(30, 5, -2), pc(100, 10, -2), pc(-70, 15, -2));
+        succeeds(pc(-30, 5, 3), pc(100, 10, 3), pc(-130, 15, 3));
+        succeeds(pc(100, 0, 0), pc(100, 0, 0), pc(0, 0, 0));
+        succeeds(pc(i64::MIN, 0, 0), pc(-1, 0, 0), pc(i64::MIN + 1, 0, 0));
+
+        // Overflow
+        fails(pc(i64::MIN, 0, 0), pc(1, 0, 0));
+        fails(pc(i64::MAX, 0, 0), pc(-1, 0, 0));
+        fails(pc(0, u64::MAX, 0), pc(0, 1, 0));
+
+        // Publish time is the minimum of the arguments
+        let price1 = Price {
+            publish_time: 100,
+            ..pc(100, 1, 0)
+        };
+        let price2 = Price {
+            publish_time: 50,
+            ..pc(30, 1, 0)
+        };
+        assert_eq!(price1.sub(&price2).unwrap().publish_time, 50);
+        assert_eq!(price2.sub(&price1).unwrap().publish_time, 50);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_sub_different_exponents() {
+        let _ = pc(100, 0, -2).sub(&pc(30, 0, -3));
+    }
+
+    #[test]
+    fn test_ops() {
+        let a = pc(100, 10, -2);
+        let b = pc(-30, 5, -2);
+        let c = pc(7, 1, 0);
+
+        assert_eq!(a + b, a.add(&b));
+        assert_eq!(a - b, a.sub(&b));
+        assert_eq!(a * c, a.mul(&c));
+        assert_eq!(a / c, a.div(&c));
+        assert_eq!((a * c).and_then(|x| x / c), a.mul(&c).unwrap().div(&c));
+
+        // Overflow is still reported as None.
+        assert_eq!(pc(i64::MAX, 0, 0) + pc(1, 0, 0), None);
+        assert_eq!(pc(0, 0, 0) - pc(i64::MIN, 0, 0), None);
+        assert_eq!(a / pc(0, 0, 0), None);
+    }
+
+    #[test]
+    #[allow(clippy::op_ref)]
+    fn test_ref_ops() {
+        let a = pc(100, 10, -2);
+        let b = pc(-30, 5, -2);
+        let c = pc(7, 1, 0);
+
+        assert_eq!(&a + &b, a + b);
+        assert_eq!(&a - &b, a - b);
+        assert_eq!(&a * &c, a * c);
+        assert_eq!(&a / &c, a / c);
+        assert_eq!((&a * &b).and_then(|x| &x / &c), a.mul(&b).unwrap().div(&c));
+
+        // Summing a basket without copying its entries.
+        let basket = [a, b, pc(5, 1, -2)];
+        let total = basket
+            .iter()
+            .try_fold(pc(0, 0, -2), |acc, price| &acc + price);
+        assert_eq!(total, Some(pc(75, 16, -2)));
+
+        assert_eq!(&pc(1, 0, i32::MAX) * &pc(1, 0, 1), None);
+        assert_eq!(&a / &pc(0, 0, 0), None);
+    }
+
+    fn is_isqrt(n: u128, root: u128) -> bool {
+        root * root <= n
+            && (root + 1)
+                .checked_mul(root + 1)
+                .map_or(true, |next| next > n)
+    }
+
+    #[test]
+    fn test_u128_isqrt() {
+        for n in 0..1000u128 {
+            assert!(is_isqrt(n, u128_isqrt(n)), "isqrt({})", n);
+        }
+
+        // Perfect squares and their neighbors
+        for root in [
+            1u128,
+            2,
+            3,
+            10,
+            12345,
+            1 << 32,
+            u64::MAX as u128 - 1,
+            u64::MAX as u128,
+        ] {
+            let square = root * root;
+            assert_eq!(u128_isqrt(square), root);
+            assert_eq!(u128_isqrt(square - 1), root - 1);
+            if let Some(next) = square.checked_add(1) {
+                assert_eq!(u128_isqrt(next), root);
+            }
+            if let Some(next) = square.checked_add(2 * root) {
+                assert_eq!(u128_isqrt(next), root);
+            }
+        }
+
+        assert_eq!(u128_isqrt(u128::MAX), u64::MAX as u128);
+        assert_eq!(u128_isqrt(u128::MAX - 1), u64::MAX as u128);
+        assert_eq!(u128_isqrt(1 << 126), 1 << 63);
+        assert_eq!(u128_isqrt((1 << 126) - 1), (1 << 63) - 1);
+        assert_eq!(u128_isqrt(1 << 127), 13043817825332782212);
+    }
+
+    #[quickcheck]
+    fn quickcheck_u128_isqrt(n: u128) -> bool {
+        is_isqrt(n, u128_isqrt(n))
+    }
+
+    #[quickcheck]
+    fn quickcheck_u128_isqrt_near_squares(root: u64) -> bool {
+        let root = root as u128;
+        let square = root * root;
+        u128_isqrt(square) == root && u128_isqrt(square + 2 * root) == root
+    }
+
+    #[test]
+    fn test_geometric_mean() {
+        fn succeeds(a: Price, b: Price, result_expo: i32, expected: Price) {
+            assert_eq!(
+                Price::geometric_mean(&a, &b, result_expo).unwrap(),
+                expected
+            );
+            assert_eq!(
+                Price::geometric_mean(&b, &a, result_expo).unwrap(),
+                expected
+            );
+        }
+
+        fn fails(a: Price, b: Price, result_expo: i32) {
+            assert_eq!(Price::geometric_mean(&a, &b, result_expo), None);
+        }
+
+        // Equal inputs return the input
+        succeeds(pc(100, 10, -2), pc(100, 10, -2), -2, pc(100, 10, -2));
+        succeeds(pc(12345, 0, 3), pc(12345, 0, 3), 3, pc(12345, 0, 3));
+        succeeds(pc(100, 10, -2), pc(100, 10, -2), -4, pc(10000, 1000, -4));
+
+        succeeds(pc(4, 0, 0), pc(9, 0, 0), 0, pc(6, 0, 0));
+        succeeds(pc(4, 0, 0), pc(9, 0, 0), -2, pc(600, 0, -2));
+        succeeds(pc(400, 0, -2), pc(9, 0, 0), -1, pc(60, 0, -1));
+        // sqrt(2) = 1.41421356..., the confidence accounts for rounding
+        succeeds(pc(1, 0, 0), pc(2, 0, 0), -8, pc(141421356, 1, -8));
+        succeeds(pc(0, 0, 0), pc(2, 0, 0), -8, pc(0, 0, -8));
+
+        // The confidence covers both ends of the interval: sqrt(5 * 10) = 7.07 and
+        // sqrt(3 * 8) = 4.89 around sqrt(4 * 9) = 6.
+        succeeds(pc(4, 1, 0), pc(9, 1, 0), 0, pc(6, 2, 0));
+        succeeds(pc(4, 1, 0), pc(9, 1, 0), -2, pc(600, 111, -2));
+        succeeds(pc(0, 4, 0), pc(9, 0, 0), -2, pc(0, 600, -2));
+
+        // Publish time is the earlier of the two
+        assert_eq!(
+            Price::geometric_mean(
+                &Price {
+                    publish_time: 100,
+                    ..pc(4, 0, 0)
+                },
+                &Price {
+                    publish_time: 90,
+                    ..pc(9, 0, 0)
+                },
+                0
+            )
+            .unwrap()
+            .publish_time,
+            90
+        );
+
+        // Extreme values
+        succeeds(
+            pc(i64::MAX, 0, 0),
+            pc(i64::MAX, 0, 0),
+            0,
+            pc(i64::MAX, 0, 0),
+        );
+        succeeds(
+            pc(1, 0, i32::MAX),
+            pc(1, 0, i32::MAX),
+            i32::MAX,
+            pc(1, 0, i32::MAX),
+        );
+        succeeds(
+            pc(1, 0, i32::MIN),
+            pc(1, 0, i32::MIN),
+            i32::MIN,
+            pc(1, 0, i32::MIN),
+        );
+        succeeds(pc(1, 0, -100), pc(1, 0, -100), 0, pc(0, 0, 0));
+
+        // Negative inputs
+        fails(pc(-4, 0, 0), pc(9, 0, 0), 0);
+        fails(pc(4, 0, 0), pc(-9, 0, 0), 0);
+        // Overflow
+        fails(pc(i64::MAX, 0, 0), pc(i64::MAX, 0, 0), -1);
+        fails(pc(1, 0, 0), pc(1, 0, 0), -20);
+        fails(pc(i64::MAX, u64::MAX, 0), pc(i64::MAX, u64::MAX, 0), 0);
+    }
+
+    #[test]
+    fn test_price_basket_l2() {
+        let amounts = [
+            (
+                Price {
+                    publish_time: 100,
+                    ..pc(10000, 30, -2)
+                },
+                2,
+                0,
+            ),
+            (
+                Price {
+                    publish_time: 90,
+                    ..pc(5000, 40, -2)
+                },
+                20,
+                -1,
+            ),
+        ];
+
+        let l1 = Price::price_basket(&amounts, -2).unwrap();
+        let l2 = Price::price_basket_l2(&amounts, -2).unwrap();
+        assert_eq!(
+            l2,
+            Price {
+                publish_time: 90,
+                ..pc(30000, 100, -2)
+            }
+        );
+        assert_eq!(l1.price, l2.price);
+        assert_eq!(l1.conf, 140);
+        assert!(l2.conf < l1.conf);
+
+        // A single entry has the same confidence under both norms
+        assert_eq!(
+            Price::price_basket_l2(&amounts[..1], -4),
+            Price::price_basket(&amounts[..1], -4)
+        );
+
+        // The square root is rounded down
+        assert_eq!(
+            Price::price_basket_l2(&[(pc(1, 1, 0), 1, 0), (pc(1, 1, 0), 1, 0)], 0),
+            Some(pc(2, 1, 0))
+        );
+
+        // Confidences as large as u64::MAX don't overflow the sum of squares
+        assert_eq!(
+            Price::price_basket_l2(&[(pc(0, u64::MAX, 0), 1, 0)], 0),
+            Price::price_basket(&[(pc(0, u64::MAX, 0), 1, 0)], 0)
+        );
+
+        assert_eq!(Price::price_basket_l2(&[], 0), None);
+        assert_eq!(
+            Price::price_basket_l2(&[(pc(i64::MAX, 0, 0), 1, 0); 2], 0),
+            None
+        );
+        assert_eq!(Price::price_basket_l2(&amounts, -18), None);
+    }
+
+    #[test]
+    fn test_vwap() {
+        fn succeeds(fills: &[(Price, i64)], result_expo: i32, expected: Price) {
+            assert_eq!(Price::vwap(fills, result_expo).unwrap(), expected);
+        }
+
+        fn fails(fills: &[(Price, i64)], result_expo: i32) {
+            assert_eq!(Price::vwap(fills, result_expo), None);
+        }
+
+        succeeds(&[(pc(100, 0, 0), 1)], 0, pc(100, 0, 0));
+        succeeds(&[(pc(100, 0, 0), 1), (pc(200, 0, 0), 3)], 0, pc(175, 0, 0));
+        succeeds(&[(pc(100, 4, 0), 1), (pc(200, 2, 0), 3)], 0, pc(175, 2, 0));
+        succeeds(
+            &[(pc(100, 0, 0), 1), (pc(200, 0, 0), 3)],
+            -2,
+            pc(17500, 0, -2),
+        );
+
+        // Partially closing a position: buy 10 at 100.00, sell 4 at 110.00.
+        succeeds(
+            &[(pc(10000, 10, -2), 10), (pc(11000, 10, -2), -4)],
+            -2,
+            pc(9333, 23, -2),
+        );
+
+        // A short position has a positive entry price too.
+        succeeds(
+            &[(pc(100, 0, 0), -2), (pc(130, 0, 0), -1)],
+            0,
+            pc(110, 0, 0),
+        );
+
+        // Empty fills or a flat position
+        fails(&[], 0);
+        fails(&[(pc(100, 0, 0), 1), (pc(110, 0, 0), -1)], 0);
+
+        // Overflow
+        fails(&[(pc(i64::MAX, 0, 0), 2)], 0);
+        fails(&[(pc(1, 0, 0), i64::MAX), (pc(1, 0, 0), 1)], -2);
+    }
+
+    #[test]
+    fn test_midrate() {
+        fn succeeds(bid: Price, ask: Price, result_expo: i32, expected: Price) {
+            assert_eq!(Price::midrate(&bid, &ask, result_expo).unwrap(), expected);
+        }
+
+        fn fails(bid: Price, ask: Price, result_expo: i32) {
+            assert_eq!(Price::midrate(&bid, &ask, result_expo), None);
+        }
+
+        // Symmetric bid/ask
+        succeeds(pc(9900, 10, -2), pc(10100, 10, -2), -2, pc(10000, 110, -2));
+        succeeds(
+            pc(9900, 10, -2),
+            pc(10100, 10, -2),
+            -3,
+            pc(100000, 1100, -3),
+        );
+        succeeds(pc(100, 0, 0), pc(100, 0, 0), 0, pc(100, 0, 0));
+
+        // Skewed bid/ask
+        succeeds(pc(99, 10, 0), pc(105, 2, 0), 0, pc(102, 9, 0));
+        succeeds(pc(99, 0, 0), pc(10500, 0, -2), -2, pc(10200, 300, -2));
+
+        // Crossed bid/ask still report the spread in the confidence
+        succeeds(pc(105, 0, 0), pc(99, 0, 0), 0, pc(102, 3, 0));
+
+        // Publish time is the minimum of the inputs
+        let bid = Price {
+            publish_time: 100,
+            ..pc(99, 1, 0)
+        };
+        let ask = Price {
+            publish_time: 90,
+            ..pc(101, 1, 0)
+        };
+        assert_eq!(Price::midrate(&bid, &ask, 0).unwrap().publish_time, 90);
+
+        // Overflow
+        fails(pc(i64::MAX, 0, 0), pc(i64::MAX, 0, 0), 0);
+        fails(pc(i64::MIN, 0, 0), pc(i64::MAX, 0, 0), 0);
+        fails(pc(100, 0, 0), pc(100, 0, 0), -18);
+    }
+
+    #[test]
+    fn test_twap_samples() {
+        fn succeeds(samples: &[(i64, Price)], result_expo: i32, expected: Price) {
+            assert_eq!(Price::twap_samples(samples, result_expo).unwrap(), expected);
+        }
+
+        fn fails(samples: &[(i64, Price)], result_expo: i32) {
+            assert_eq!(Price::twap_samples(samples, result_expo), None);
+        }
+
+        succeeds(
+            &[(0, pc(100, 10, 0)), (10, pc(100, 10, 0))],
+            0,
+            pc(100, 10, 0),
+        );
+        succeeds(
+            &[(0, pc(100, 0, 0)), (10, pc(200, 20, 0))],
+            0,
+            pc(150, 10, 0),
+        );
+
+        // (10 * (100 + 200) / 2 + 20 * (200 + 200) / 2) / 30 = 5500 / 30 = 183.33...
+        succeeds(
+            &[(0, pc(100, 4, 0)), (10, pc(200, 2, 0)), (30, pc(200, 2, 0))],
+            0,
+            pc(183, 2, 0),
+        );
+        succeeds(
+            &[(0, pc(100, 4, 0)), (10, pc(200, 2, 0)), (30, pc(200, 2, 0))],
+            -2,
+            pc(18333, 233, -2),
+        );
+
+        // Samples with different exponents
+        succeeds(
+            &[(100, pc(1000, 0, -1)), (110, pc(200, 0, 0))],
+            -1,
+            pc(1500, 0, -1),
+        );
+
+        // Samples at the same time contribute nothing
+        succeeds(
+            &[(0, pc(100, 0, 0)), (0, pc(500, 0, 0)), (10, pc(100, 0, 0))],
+            0,
+            pc(300, 0, 0),
+        );
+
+        // The publish time is the minimum of the samples
+        let samples = [
+            (
+                0,
+                Price {
+                    publish_time: 7,
+                    ..pc(100, 0, 0)
+                },
+            ),
+            (
+                10,
+                Price {
+                    publish_time: 5,
+                    ..pc(100, 0, 0)
+                },
+            ),
+        ];
+        assert_eq!(Price::twap_samples(&samples, 0).unwrap().publish_time, 5);
+
+        // Not enough samples
+        fails(&[], 0);
+        fails(&[(0, pc(100, 0, 0))], 0);
+
+        // Unsorted samples or no elapsed time
+        fails(&[(10, pc(100, 0, 0)), (0, pc(100, 0, 0))], 0);
+        fails(&[(10, pc(100, 0, 0)), (10, pc(100, 0, 0))], 0);
+
+        // Overflow
+        fails(&[(0, pc(100, 0, 0)), (10, pc(100, 0, 0))], -18);
+        fails(&[(i64::MIN, pc(100, 0, 0)), (i64::MAX, pc(100, 0, 0))], 0);
+    }
+
+    #[test]
+    fn test_with_fee_bps() {
+        fn succeeds(price: Price, fee_bps: i64, result_expo: i32, expected: Price) {
+            assert_eq!(price.with_fee_bps(fee_bps, result_expo).unwrap(), expected);
+        }
+
+        fn fails(price: Price, fee_bps: i64, result_expo: i32) {
+            assert_eq!(price.with_fee_bps(fee_bps, result_expo), None);
+        }
+
+        // Positive fee, e.g. for an ask price
+        succeeds(pc(10000, 100, -2), 30, -2, pc(10030, 100, -2));
+        succeeds(pc(10000, 100, -2), 30, -4, pc(1003000, 10030, -4));
+        succeeds(pc(10000, 100, -2), 10_000, -2, pc(20000, 200, -2));
+
+        // Negative fee, e.g. for a bid price
+        succeeds(pc(10000, 100, -2), -30, -4, pc(997000, 9970, -4));
+        succeeds(pc(10000, 100, -2), -10_000, -2, pc(0, 0, -2));
+
+        // No fee
+        succeeds(pc(10000, 100, -2), 0, -2, pc(10000, 100, -2));
+        succeeds(pc(-10000, 100, -2), 30, -2, pc(-10030, 100, -2));
+
+        // Fee below -100%
+        fails(pc(10000, 100, -2), -10_001, -2);
+        fails(pc(10000, 100, -2), i64::MAX, -2);
+
+        // Overflow
+        fails(pc(10000, 100, -2), 30, -18);
+    }
+
+    #[test]
+    fn test_from_price_conf_json() {
+        let legacy = serde_json::json!({
+            "price": "-12345",
+            "conf": "267",
+            "expo": -2
+        });
+        let price = Price::from_price_conf_json(legacy).unwrap();
+        assert_eq!(
+            price,
+            Price {
+                price:        -12345,
+                conf:         267,
+                expo:         -2,
+                publish_time: 0,
+            }
+        );
+
+        // The migrated price round-trips through the current representation.
+        let json = serde_json::to_value(price).unwrap();
+        assert_eq!(json["publish_time"], serde_json::json!(0));
+        assert_eq!(serde_json::from_value::<Price>(json).unwrap(), price);
+
+        // Large numbers are preserved.
+        let legacy = serde_json::json!({
+            "price": "1000000000000000123",
+            "conf": "1234567000000000789",
+            "expo": -8
+        });
+        let price = Price::from_price_conf_json(legacy).unwrap();
+        assert_eq!(price.price, 1_000_000_000_000_000_123);
+        assert_eq!(price.conf, 1_234_567_000_000_000_789);
+
+        // Numbers are accepted in place of strings, like for `Price`.
+        assert_eq!(
+            Price::from_price_conf_json(serde_json::json!({"price": 1, "conf": "1", "expo": 0}))
+                .unwrap(),
+            Price {
+                price:        1,
+                conf:         1,
+                expo:         0,
+                publish_time: 0,
+            }
+        );
+
+        // Malformed payloads are rejected.
+        assert!(Price::from_price_conf_json(serde_json::json!({"price": "1", "expo": 0})).is_err());
+        assert!(Price::from_price_conf_json(
+            serde_json::json!({"price": "abc", "conf": "1", "expo": 0})
+        )
+        .is_err());
+        assert!(Price::from_price_conf_json(
+            serde_json::json!({"price": "1", "conf": "-1", "expo": 0})
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_from_price_conf_json_field_attribute() {
+        #[derive(serde::Deserialize)]
+        struct LegacyRecord {
+            #[serde(deserialize_with = "Price::from_price_conf_json")]
+            price: Price,
+        }
+
+        let record: LegacyRecord =
+            serde_json::from_str(r#"{"price": {"price": "100", "conf": "2", "expo": -1}}"#)
+                .unwrap();
+        assert_eq!(
+            record.price,
+            Price {
+                price:        100,
+                conf:         2,
+                expo:         -1,
+                publish_time: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_confidence_intervals_overlap() {
+        // Clearly overlapping: [95, 105] and [100, 110]
+        assert_eq!(
+            pc(100, 5, 0).confidence_intervals_overlap(&pc(105, 5, 0)),
+            Some(true)
+        );
+        // One interval contains the other
+        assert_eq!(
+            pc(100, 50, 0).confidence_intervals_overlap(&pc(110, 1, 0)),
+            Some(true)
+        );
+        // Clearly disjoint: [95, 105] and [195, 205]
+        assert_eq!(
+            pc(100, 5, 0).confidence_intervals_overlap(&pc(200, 5, 0)),
+            Some(false)
+        );
+        assert_eq!(
+            pc(200, 5, 0).confidence_intervals_overlap(&pc(100, 5, 0)),
+            Some(false)
+        );
+        // Just touching: [95, 105] and [105, 115]
+        assert_eq!(
+            pc(100, 5, 0).confidence_intervals_overlap(&pc(110, 5, 0)),
+            Some(true)
+        );
+        assert_eq!(
+            pc(110, 5, 0).confidence_intervals_overlap(&pc(100, 5, 0)),
+            Some(true)
+        );
+        // Just apart: [95, 105] and [106, 116]
+        assert_eq!(
+            pc(100, 5, 0).confidence_intervals_overlap(&pc(111, 5, 0)),
+            Some(false)
+        );
+
+        // Different exponents: [1.00, 1.10] and [1.100, 1.200] touch, [1.101, 1.201] don't
+        assert_eq!(
+            pc(105, 5, -2).confidence_intervals_overlap(&pc(1150, 50, -3)),
+            Some(true)
+        );
+        assert_eq!(
+            pc(105, 5, -2).confidence_intervals_overlap(&pc(1151, 50, -3)),
+            Some(false)
+        );
+
+        // Zero confidence and negative prices
+        assert_eq!(
+            pc(-100, 0, 0).confidence_intervals_overlap(&pc(-100, 0, 0)),
+            Some(true)
+        );
+        assert_eq!(
+            pc(-100, 10, 0).confidence_intervals_overlap(&pc(-80, 10, 0)),
+            Some(true)
+        );
+        assert_eq!(
+            pc(-100, 0, 0).confidence_intervals_overlap(&pc(100, 0, 0)),
+            Some(false)
+        );
+
+        // Bounds beyond the range of an i64
+        assert_eq!(
+            pc(i64::MAX, u64::MAX, 0).confidence_intervals_overlap(&pc(i64::MIN, u64::MAX, 0)),
+            Some(true)
+        );
+        assert_eq!(
+            pc(i64::MAX, 0, 0).confidence_intervals_overlap(&pc(i64::MIN, 0, 0)),
+            Some(false)
+        );
+
+        // Overflow when rescaling
+        assert_eq!(
+            pc(i64::MAX, 0, 0).confidence_intervals_overlap(&pc(1, 0, -1)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_min_max_value() {
+        let a = pc(12345, 10, -2);
+        let b = pc(123460, 20, -3);
+
+        assert_eq!(a.min_value(&b), Some(a));
+        assert_eq!(b.min_value(&a), Some(a));
+        assert_eq!(a.max_value(&b), Some(b));
+        assert_eq!(b.max_value(&a), Some(b));
+
+        // Negative values
+        let c = pc(-1, 0, 2);
+        assert_eq!(a.min_value(&c), Some(c));
+        assert_eq!(a.max_value(&c), Some(a));
+
+        // Equal values at different exponents return this price
+        let d = pc(123450, 30, -3);
+        assert_eq!(a.min_value(&d), Some(a));
+        assert_eq!(d.min_value(&a), Some(d));
+        assert_eq!(a.max_value(&d), Some(a));
+        assert_eq!(d.max_value(&a), Some(d));
+
+        // Rescale overflow
+        let e = pc(i64::MAX, 0, 0);
+        let f = pc(1, 0, -1);
+        assert_eq!(e.min_value(&f), None);
+        assert_eq!(f.max_value(&e), None);
+    }
+
+    #[test]
+    fn test_get_amount_for_value() {
+        fn succeeds(price: Price, value: Price, amount_expo: i32, expected: u64) {
+            assert_eq!(
+                price.get_amount_for_value(value, amount_expo),
+                Some(expected)
+            );
+        }
+
+        fn fails(price: Price, value: Price, amount_expo: i32) {
+            assert_eq!(price.get_amount_for_value(value, amount_expo), None);
+        }
+
+        // $250 of a $100 asset
+        succeeds(pc(10000, 10, -2), pc(25000, 0, -2), -6, 2_500_000);
+        succeeds(pc(10000, 10, -2), pc(250, 0, 0), -6, 2_500_000);
+        succeeds(pc(10000, 10, -2), pc(25000, 0, -2), 0, 2);
+        succeeds(pc(100, 0, 0), pc(0, 0, 0), -6, 0);
+
+        // Inverse of `get_quantity_value`
+        let price = pc(123456, 0, -3);
+        let value = price.get_quantity_value(2_000_000, -6, -3).unwrap();
+        succeeds(price, value, -6, 2_000_000);
+
+        // Zero price
+        fails(pc(0, 10, -2), pc(25000, 0, -2), -6);
+        // Negative amounts
+        fails(pc(-10000, 10, -2), pc(25000, 0, -2), -6);
+        fails(pc(10000, 10, -2), pc(-25000, 0, -2), -6);
+        // Overflow
+        fails(pc(1, 0, -2), pc(i64::MAX, 0, 0), -18);
+    }
+
+    #[test]
+    fn test_is_older_than() {
+        let price = Price {
+            publish_time: 100,
+            ..pc(10000, 10, -2)
+        };
+
+        assert!(!price.is_older_than(100, 0));
+        assert!(!price.is_older_than(110, 10));
+        assert!(price.is_older_than(111, 10));
+
+        // Prices published in the future are treated like in `get_price_no_older_than`
+        assert!(!price.is_older_than(90, 10));
+        assert!(price.is_older_than(89, 10));
+
+        // No overflow for extreme times
+        assert!(price.is_older_than(i64::MIN, 1 << 63));
+        assert!(!price.is_older_than(i64::MIN, u64::MAX));
+    }
+
+    #[test]
+    fn test_confidence_ratio() {
+        assert_eq!(pc(10000, 100, -2).confidence_ratio(), Some(0.01));
+        assert_eq!(pc(-10000, 100, -2).confidence_ratio(), Some(0.01));
+        assert_eq!(pc(10000, 100, 5).confidence_ratio(), Some(0.01));
+        assert_eq!(pc(10000, 0, -2).confidence_ratio(), Some(0.0));
+        assert_eq!(pc(1, 3, 0).confidence_ratio(), Some(3.0));
+        assert_eq!(pc(i64::MIN, 0, 0).confidence_ratio(), Some(0.0));
+        assert_eq!(pc(0, 100, -2).confidence_ratio(), None);
+    }
+
+    #[test]
+    fn test_to_f64() {
+        fn succeeds(price: Price, expected_price: f64, expected_conf: f64) {
+            let epsilon = 1e-9 * expected_price.abs().max(1.0);
+            assert!((price.to_f64() - expected_price).abs() <= epsilon);
+            let epsilon = 1e-9 * expected_conf.abs().max(1.0);
+            assert!((price.conf_to_f64() - expected_conf).abs() <= epsilon);
+        }
+
+        succeeds(pc(12345678900, 1230000, -8), 123.456789, 0.0123);
+        succeeds(pc(-12345678900, 1230000, -8), -123.456789, 0.0123);
+        succeeds(pc(123, 1, 2), 12300.0, 100.0);
+        succeeds(pc(123, 1, 0), 123.0, 1.0);
+        succeeds(pc(0, 0, -5), 0.0, 0.0);
+        succeeds(pc(i64::MAX, u64::MAX, 0), i64::MAX as f64, u64::MAX as f64);
+
+        // Extreme exponents saturate instead of panicking.
+        assert_eq!(pc(1, 1, i32::MIN).to_f64(), 0.0);
+        assert_eq!(pc(1, 1, i32::MAX).to_f64(), f64::INFINITY);
+    }
+
+    #[test]
+    fn test_from_f64() {
+        fn succeeds(value: f64, conf: f64, expo: i32, expected: Price) {
+            assert_eq!(Price::from_f64(value, conf, expo, 0).unwrap(), expected);
+        }
+
+        fn fails(value: f64, conf: f64, expo: i32) {
+            assert_eq!(Price::from_f64(value, conf, expo, 0), None);
+        }
+
+        succeeds(123.456789, 0.0123, -8, pc(12345678900, 1230000, -8));
+        succeeds(-123.456789, 0.0123, -8, pc(-12345678900, 1230000, -8));
+        succeeds(12300.0, 100.0, 2, pc(123, 1, 2));
+        succeeds(123.0, 1.0, 0, pc(123, 1, 0));
+        succeeds(0.0, 0.0, -5, pc(0, 0, -5));
+
+        // Rounds to the nearest integer
+        succeeds(1.234, 0.016, -2, pc(123, 2, -2));
+        succeeds(1.235, 0.0, -1, pc(12, 0, -1));
+        succeeds(-1.25, 0.0, -1, pc(-13, 0, -1));
+        succeeds(12345.0, 0.0, 3, pc(12, 0, 3));
+        succeeds(1e-300, 0.0, 0, pc(0, 0, 0));
+
+        // Publish time is preserved
+        assert_eq!(Price::from_f64(1.0, 0.0, 0, 123).unwrap().publish_time, 123);
+
+        // NaN and infinity
+        fails(f64::NAN, 0.0, -8);
+        fails(1.0, f64::NAN, -8);
+        fails(f64::INFINITY, 0.0, -8);
+        fails(f64::NEG_INFINITY, 0.0, -8);
+        fails(1.0, f64::INFINITY, -8);
+
+        // Negative confidence
+        fails(1.0, -0.1, -8);
+
+        // Overflow
+        fails(1e30, 0.0, -8);
+        fails(-1e30, 0.0, -8);
+        fails(1.0, 1e30, -8);
+        fails(9_223_372_036_854_775_808.0, 0.0, 0);
+        fails(1.0, 0.0, i32::MIN);
+
+        // Round trip
+        for (value, expo) in [(123.456789, -8), (-0.000123, -10), (5.5e12, 3)].iter() {
+            let price = Price::from_f64(*value, 0.0, *expo, 0).unwrap();
+            let tolerance = 0.5 * 10f64.powi(*expo) + 1e-12 * value.abs();
+            assert!((price.to_f64() - value).abs() <= tolerance);
+        }
+    }
+
+    #[test]
+    fn test_band_crosses_zero() {
+        assert!(!pc(100, 10, -2).band_crosses_zero());
+        assert!(!pc(-100, 99, -2).band_crosses_zero());
+        assert!(!pc(i64::MIN, u64::MAX / 2, 0).band_crosses_zero());
+
+        assert!(pc(100, 100, -2).band_crosses_zero());
+        assert!(pc(-100, 150, -2).band_crosses_zero());
+        assert!(pc(0, 0, 0).band_crosses_zero());
+        assert!(pc(i64::MIN, u64::MAX, 0).band_crosses_zero());
+    }
+
+    #[test]
+    fn test_abs() {
+        let price = Price {
+            publish_time: 100,
+            ..pc(-12345, 267, -2)
+        };
+        assert_eq!(
+            price.abs(),
+            Some(Price {
+                publish_time: 100,
+                ..pc(12345, 267, -2)
+            })
+        );
+
+        assert_eq!(pc(12345, 267, -2).abs(), Some(pc(12345, 267, -2)));
+        assert_eq!(pc(0, 1, 3).abs(), Some(pc(0, 1, 3)));
+        assert_eq!(pc(i64::MAX, 0, 0).abs(), Some(pc(i64::MAX, 0, 0)));
+        assert_eq!(pc(-i64::MAX, 0, 0).abs(), Some(pc(i64::MAX, 0, 0)));
+        assert_eq!(pc(i64::MIN, 0, 0).abs(), None);
+    }
+
+    #[test]
+    fn test_display_decimals() {
+        assert_eq!(pc(12345, 267, -2).display_decimals(), 2);
+        assert_eq!(pc(12300, 250, -3).display_decimals(), 2);
+        assert_eq!(pc(12300, 255, -3).display_decimals(), 3);
+        assert_eq!(pc(-12345, 200, -3).display_decimals(), 3);
+        assert_eq!(pc(12300, 0, -2).display_decimals(), 0);
+        assert_eq!(pc(0, 0, -8).display_decimals(), 0);
+        assert_eq!(pc(0, 1, -8).display_decimals(), 8);
+        assert_eq!(pc(1234567890, 100000, -8).display_decimals(), 7);
+        assert_eq!(pc(123, 1, 0).display_decimals(), 0);
+        assert_eq!(pc(123, 1, 2).display_decimals(), 0);
+        assert_eq!(pc(i64::MIN, u64::MAX, -5).display_decimals(), 5);
+    }
+
+    #[test]
+    fn test_cmp_value() {
+        use std::cmp::Ordering;
+
+        assert_eq!(
+            pc(12345000, 1, -8).cmp_value(&pc(123450, 2, -6)),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(
+            pc(123450, 2, -6).cmp_value(&pc(12345000, 1, -8)),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(
+            pc(12345001, 0, -8).cmp_value(&pc(123450, 0, -6)),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(
+            pc(123449, 0, -6).cmp_value(&pc(12345000, 0, -8)),
+            Some(Ordering::Less)
+        );
+        assert_eq!(pc(-1, 0, 2).cmp_value(&pc(-99, 0, 0)), Some(Ordering::Less));
+        assert_eq!(pc(0, 0, 5).cmp_value(&pc(0, 0, -5)), Some(Ordering::Equal));
+
+        // Overflow when rescaling
+        assert_eq!(pc(i64::MAX, 0, 0).cmp_value(&pc(1, 0, -1)), None);
+        assert_eq!(pc(1, 0, 0).cmp_value(&pc(1, 0, -19)), None);
+
+        // Sorting by value
+        let mut prices = [pc(2, 0, 0), pc(150, 0, -2), pc(-1, 0, 1)];
+        prices.sort_by(|a, b| a.cmp_value(b).unwrap());
+        assert_eq!(prices, [pc(-1, 0, 1), pc(150, 0, -2), pc(2, 0, 0)]);
+    }
+
+    #[test]
+    fn test_normalize_pair() {
+        fn succeeds(mut a: Price, mut b: Price, expected_a: Price, expected_b: Price) {
+            assert_eq!(Price::normalize_pair(&mut a, &mut b), Some(()));
+            assert_eq!(a, expected_a);
+            assert_eq!(b, expected_b);
+        }
+
+        fn fails(mut a: Price, mut b: Price) {
+            let (orig_a, orig_b) = (a, b);
+            assert_eq!(Price::normalize_pair(&mut a, &mut b), None);
+            assert_eq!(a, orig_a);
+            assert_eq!(b, orig_b);
+        }
+
+        // A loan valued at 505.00 (expo -2) and a collateral valued at 1000.0 (expo -1), as in
+        // the loan-to-value examples.
+        succeeds(
+            pc(50500, 0, -2),
+            pc(10000, 0, -1),
+            pc(50500, 0, -2),
+            pc(100000, 0, -2),
+        );
+        succeeds(
+            pc(10000, 5, -1),
+            pc(50500, 7, -2),
+            pc(100000, 50, -2),
+            pc(50500, 7, -2),
+        );
+        succeeds(pc(1, 2, 3), pc(4, 5, 3), pc(1, 2, 3), pc(4, 5, 3));
+        succeeds(pc(-1, 0, 2), pc(3, 0, -2), pc(-10000, 0, -2), pc(3, 0, -2));
+
+        // Overflow
+        fails(pc(i64::MAX, 0, 0), pc(1, 0, -1));
+        fails(pc(1, 0, -1), pc(0, u64::MAX, 0));
+    }
+
+    #[test]
+    fn test_reciprocal() {
+        fn succeeds(price: Price, result_expo: i32, expected: Price) {
+            assert_eq!(price.reciprocal(result_expo).unwrap(), expected);
+            assert_eq!(
+                price.reciprocal(result_expo),
+                pc(1, 0, 0)
+                    .div(&price)
+                    .unwrap()
+                    .scale_to_exponent(result_expo)
+            );
+        }
+
+        fn fails(price: Price, result_expo: i32) {
+            assert_eq!(price.reciprocal(result_expo), None);
+        }
+
+        succeeds(pc(4, 0, 0), -2, pc(25, 0, -2));
+        succeeds(pc(-4, 0, 0), -2, pc(-25, 0, -2));
+        succeeds(pc(400, 4, -2), -4, pc(2500, 25, -4));
+        // USD/JPY at 150.00 +- 0.15 gives JPY/USD at 0.0066666 +- 0.0000066
+        succeeds(pc(15000, 15, -2), -8, pc(666660, 660, -8));
+
+        // Publish time is preserved
+        let price = Price {
+            publish_time: 100,
+            ..pc(4, 0, 0)
+        };
+        assert_eq!(price.reciprocal(-2).unwrap().publish_time, 100);
+
+        // Zero price
+        fails(pc(0, 0, 0), -2);
+        fails(pc(0, 10, -2), -2);
+
+        // Result cannot be represented
+        fails(pc(4, 0, 0), -20);
+    }
+
+    #[test]
+    fn test_inverse_variance_weight() {
+        assert_eq!(
+            pc(100, 1, -2).inverse_variance_weight(),
+            Some(1_000_000_000_000_000_000_000_000_000_000_000_000)
+        );
+        assert_eq!(
+            pc(100, 10, -2).inverse_variance_weight(),
+            Some(10_000_000_000_000_000_000_000_000_000_000_000)
+        );
+        assert_eq!(
+            pc(-100, 3, -2).inverse_variance_weight(),
+            Some(111_111_111_111_111_111_111_111_111_111_111_111)
+        );
+        assert_eq!(
+            pc(100, 1_000_000_000_000_000_000, -2).inverse_variance_weight(),
+            Some(1)
+        );
+        assert_eq!(pc(100, u64::MAX, -2).inverse_variance_weight(), Some(0));
+
+        // A tighter confidence interval gets a larger weight.
+        assert!(
+            pc(100, 2, -2).inverse_variance_weight() > pc(100, 3, -2).inverse_variance_weight()
+        );
+
+        // Zero confidence
+        assert_eq!(pc(100, 0, -2).inverse_variance_weight(), None);
+    }
+
+    #[test]
+    fn test_combined_confidence() {
+        // sqrt((3^2 + 4^2 + 5^2 + 0^2) / 4) = 3.53...
+        assert_eq!(
+            Price::combined_confidence(
+                &[
+                    pc(100, 3, -2),
+                    pc(101, 4, -2),
+                    pc(99, 5, -2),
+                    pc(100, 0, -2)
+                ],
+                -2
+            ),
+            Some(3)
+        );
+
+        // Confidences are scaled to the requested exponent first.
+        assert_eq!(
+            Price::combined_confidence(&[pc(100, 30, -2), pc(1000, 40, -3)], -3),
+            Some(214)
+        );
+        assert_eq!(
+            Price::combined_confidence(&[pc(100, 30, -2), pc(1000, 40, -3)], -2),
+            Some(21)
+        );
+
+        // A single price
+        assert_eq!(Price::combined_confidence(&[pc(100, 7, -2)], -2), Some(7));
+        assert_eq!(
+            Price::combined_confidence(&[pc(100, u64::MAX, -2)], -2),
+            Some(u64::MAX)
+        );
+
+        // The price itself may not be representable with the requested exponent.
+        assert_eq!(
+            Price::combined_confidence(&[pc(i64::MAX, 7, -2)], -4),
+            Some(700)
+        );
+
+        fails(&[], -2);
+        // The confidence is not representable with the requested exponent.
+        fails(&[pc(100, u64::MAX, -2)], -4);
+        // Sum of squares overflows
+        fails(&[pc(100, u64::MAX, -2), pc(100, u64::MAX, -2)], -2);
+
+        fn fails(prices: &[Price], result_expo: i32) {
+            assert_eq!(Price::combined_confidence(prices, result_expo), None);
+        }
+    }
+
+    #[test]
+    fn test_normalized_price() {
+        let prices = [
+            pc(1, 1, 0),
+            pc(-12345, 67, -2),
+            pc(i64::MAX, 1_000, -9),
+            pc(i64::MIN + 1, u64::MAX, 3),
+            pc(MAX_PD_V_I64, MAX_PD_V_I64 as u64, -5),
+            pc(0, 10, 2),
+        ];
+
+        for base in &prices {
+            let normalized = NormalizedPrice::new(base).unwrap();
+            assert_eq!(normalized.price(), base.normalize().unwrap());
+
+            // The wrapped price is already normalized, so normalizing it again is a no-op, which
+            // is the work saved on every operation.
+            assert_eq!(normalized.price().normalize(), Some(normalized.price()));
+
+            for other in &prices {
+                assert_eq!(normalized.mul(other), base.mul(other));
+                assert_eq!(normalized.div(other), base.div(other));
+            }
+
+            let other = pc(3, 4, normalized.price().expo);
+            assert_eq!(normalized.add(&other), normalized.price().add(&other));
+        }
+
+        assert_eq!(NormalizedPrice::new(&pc(i64::MAX, 0, i32::MAX)), None);
+    }
+
+    #[test]
+    fn test_representable_range() {
+        for expo in [i32::MIN, -9, 0, 9, i32::MAX] {
+            assert_eq!(Price::representable_range(expo), (i64::MIN, i64::MAX));
+        }
+    }
+
+    #[test]
+    fn test_fits_in_exponent() {
+        let prices = [
+            pc(0, 0, 0),
+            pc(0, 1, 0),
+            pc(1, 0, 0),
+            pc(-1, 1, 0),
+            pc(123, 4, -2),
+            pc(i64::MAX / 10, 0, 0),
+            pc(i64::MAX / 10 + 1, 0, 0),
+            pc(i64::MIN / 10, 0, 0),
+            pc(i64::MIN / 10 - 1, 0, 0),
+            pc(0, u64::MAX / 10, 0),
+            pc(0, u64::MAX / 10 + 1, 0),
+            pc(i64::MAX, u64::MAX, 0),
+            pc(i64::MIN, u64::MAX, 0),
+            pc(1, 1, i32::MIN),
+            pc(1, 1, i32::MAX),
+            pc(0, 0, i32::MIN),
+        ];
+        // `scale_to_exponent` is slow for zero prices and very small exponents, so the extreme
+        // exponents are checked separately below.
+        let expos = [-40, -20, -19, -18, -3, -2, -1, 0, 1, 20, i32::MAX];
+
+        for price in &prices {
+            for expo in &expos {
+                assert_eq!(
+                    price.fits_in_exponent(*expo),
+                    price.scale_to_exponent(*expo).is_some(),
+                    "{:?} to exponent {}",
+                    price,
+                    expo
+                );
+            }
+        }
+
+        assert!(pc(i64::MAX / 10, 0, 0).fits_in_exponent(-1));
+        assert!(!pc(i64::MAX / 10 + 1, 0, 0).fits_in_exponent(-1));
+        assert!(pc(0, 1, 0).fits_in_exponent(-19));
+        assert!(!pc(1, 0, 0).fits_in_exponent(-19));
+        assert!(!pc(1, 1, i32::MAX).fits_in_exponent(i32::MIN));
+        assert!(!pc(1, 1, 0).fits_in_exponent(i32::MIN));
+        assert!(pc(0, 0, 0).fits_in_exponent(i32::MIN + 1));
+        assert!(pc(1, 1, i32::MIN).fits_in_exponent(-1));
+        assert!(!pc(1, 1, i32::MIN).fits_in_exponent(i32::MAX));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(pc(12345, 267, -2).to_string(), "123.45 ± 2.67");
+        assert_eq!(pc(123, 1, 0).to_string(), "123 ± 1");
+        assert_eq!(pc(123, 1, 2).to_string(), "12300 ± 100");
+        assert_eq!(pc(-12345, 267, -2).to_string(), "-123.45 ± 2.67");
+        assert_eq!(pc(-5, 1, -3).to_string(), "-0.005 ± 0.001");
+        assert_eq!(pc(100, 0, -2).to_string(), "1.00 ± 0.00");
+        assert_eq!(pc(0, 0, -2).to_string(), "0.00 ± 0.00");
+        assert_eq!(
+            pc(i64::MIN, u64::MAX, -5).to_string(),
+            "-92233720368547.75808 ± 184467440737095.51615"
+        );
+
+        // Large exponents use scientific notation instead of writing billions of zeros
+        assert_eq!(
+            pc(1, 1, 32).to_string(),
+            "100000000000000000000000000000000 ± 100000000000000000000000000000000"
+        );
+        assert_eq!(
+            pc(1, 1, -32).to_string(),
+            "0.00000000000000000000000000000001 ± 0.00000000000000000000000000000001"
+        );
+        assert_eq!(pc(-123, 1, 33).to_string(), "-123e33 ± 1e33");
+        assert_eq!(
+            pc(1, 1, i32::MAX).to_string(),
+            "1e2147483647 ± 1e2147483647"
+        );
+        assert_eq!(
+            pc(1, 1, i32::MIN).to_string(),
+            "1e-2147483648 ± 1e-2147483648"
+        );
+    }
+
+    #[test]
+    fn test_round_to_significant_figures() {
+        fn succeeds(price: Price, figures: u32, expected: Price) {
+            assert_eq!(
+                price.round_to_significant_figures(figures).unwrap(),
+                expected
+            );
+        }
+
+        fn fails(price: Price, figures: u32) {
+            assert_eq!(price.round_to_significant_figures(figures), None);
+        }
+
+        succeeds(pc(123456, 0, -3), 3, pc(123, 0, 0));
+        succeeds(pc(123456, 0, -3), 4, pc(1235, 0, -1));
+        succeeds(pc(-123456, 0, -3), 4, pc(-1235, 0, -1));
+        succeeds(pc(123449, 0, -3), 4, pc(1234, 0, -1));
+        succeeds(pc(123456, 0, -3), 1, pc(1, 0, 2));
+
+        // Confidence is scaled and rounded up.
+        succeeds(pc(123456, 1001, -3), 3, pc(123, 2, 0));
+        succeeds(pc(123456, 1000, -3), 3, pc(123, 1, 0));
+        succeeds(pc(123456, 1, -3), 3, pc(123, 1, 0));
+
+        // Rounding carries into an additional digit.
+        succeeds(pc(999, 0, 0), 2, pc(10, 0, 2));
+        succeeds(pc(-9999, 15, -2), 1, pc(-1, 1, 2));
+
+        // Already within the requested number of figures
+        succeeds(pc(123, 4, -2), 3, pc(123, 4, -2));
+        succeeds(pc(123, 4, -2), 10, pc(123, 4, -2));
+        succeeds(pc(0, 4, -2), 1, pc(0, 4, -2));
+
+        // Extreme values
+        succeeds(pc(i64::MAX, u64::MAX, 0), 1, pc(9, 19, 18));
+        succeeds(pc(i64::MIN, 0, 0), 2, pc(-92, 0, 17));
+
+        fails(pc(123, 4, -2), 0);
+        fails(pc(123, 4, i32::MAX), 1);
+    }
+
+    #[test]
+    fn test_try_arithmetic_errors() {
+        // Successful operations agree with the `Option` methods
+        assert_eq!(
+            pc(10, 1, 0).try_div(&pc(2, 0, 0)).ok(),
+            pc(10, 1, 0).div(&pc(2, 0, 0))
+        );
+        assert_eq!(
+            pc(10, 1, 0).try_mul(&pc(2, 0, 0)).ok(),
+            pc(10, 1, 0).mul(&pc(2, 0, 0))
+        );
+        assert_eq!(pc(10, 1, 0).try_add(&pc(2, 0, 0)), Ok(pc(12, 1, 0)));
+
+        // Division by zero
+        assert_eq!(
+            pc(1, 0, 0).try_div(&pc(0, 0, 0)),
+            Err(OracleError::DivByZero)
+        );
+        assert_eq!(
+            pc(1, 0, 0).try_div(&pc(0, 100, -5)),
+            Err(OracleError::DivByZero)
+        );
+        assert_eq!(pc(1, 0, 0).div(&pc(0, 0, 0)), None);
+
+        // The confidence of the divisor is much larger than its price
+        assert_eq!(
+            pc(MAX_PD_V_I64, 0, 0).try_div(&pc(1, MAX_PD_V_U64, 0)),
+            Err(OracleError::ConfidenceTooWide)
+        );
+        assert_eq!(pc(MAX_PD_V_I64, 0, 0).div(&pc(1, MAX_PD_V_U64, 0)), None);
+
+        // Exponent overflow
+        assert_eq!(
+            pc(1, 0, i32::MAX).try_div(&pc(1, 0, i32::MIN)),
+            Err(OracleError::Overflow)
+        );
+        assert_eq!(
+            pc(1, 0, i32::MAX).try_mul(&pc(1, 0, 1)),
+            Err(OracleError::Overflow)
+        );
+        assert_eq!(
+            pc(i64::MAX, 0, i32::MAX).try_mul(&pc(1, 0, 0)),
+            Err(OracleError::Overflow)
+        );
+        assert_eq!(pc(1, 0, i32::MAX).mul(&pc(1, 0, 1)), None);
+
+        // Sum overflow
+        assert_eq!(
+            pc(i64::MAX, 0, 0).try_add(&pc(1, 0, 0)),
+            Err(OracleError::Overflow)
+        );
+        assert_eq!(
+            pc(0, u64::MAX, 0).try_add(&pc(0, 1, 0)),
+            Err(OracleError::Overflow)
+        );
+        assert_eq!(pc(i64::MAX, 0, 0).add(&pc(1, 0, 0)), None);
+
+        // Different exponents
+        assert_eq!(
+            pc(100, 0, -2).try_add(&pc(30, 0, -3)),
+            Err(OracleError::ExponentMismatch)
+        );
+        assert_eq!(pc(100, 0, -2).add(&pc(30, 0, -3)), None);
+        assert_eq!(pc(100, 0, -2) + pc(30, 0, -3), None);
+    }
+
+    #[test]
+    fn test_div_conf_paths() {
+        // The confidence of `div` is computed with u64 arithmetic when possible and falls back to
+        // u128 otherwise. Both must agree with the full-width computation.
+        fn reference_conf(base: Price, other: Price) -> u128 {
+            let (base, other) = (base.normalize().unwrap(), other.normalize().unwrap());
+            let (base_price, _) = Price::to_unsigned(base.price);
+            let (other_price, _) = Price::to_unsigned(other.price);
+            let midprice = (base_price as u128) * (PD_SCALE as u128) / (other_price as u128);
+            let pct = (other.conf as u128) * (PD_SCALE as u128) / (other_price as u128);
+            (base.conf as u128) * (PD_SCALE as u128) / (other_price as u128)
+                + pct * midprice / (PD_SCALE as u128)
+        }
+
+        let cases = [
+            (pc(100, 1, -2), pc(3, 1, 0)),
+            (pc(MAX_PD_V_I64, 1, 0), pc(1, 1, 0)),
+            (pc(MAX_PD_V_I64, 1, 0), pc(1, 50, 0)),
+            (pc(1, 1, 0), pc(MAX_PD_V_I64, MAX_PD_V_U64, 0)),
+            (pc(i64::MAX, 1, 0), pc(1, 10, 0)),
+            (pc(-123456789, 4567, -5), pc(987, 6, -2)),
+        ];
+
+        for (base, other) in cases.iter() {
+            assert_eq!(
+                base.div(other).unwrap().conf as u128,
+                reference_conf(*base, *other)
+            );
+        }
+    }
+
+    #[test]
+    fn test_get_quantity_value() {
+        fn succeeds(
+            price: Price,
+            amount: u64,
+            amount_expo: i32,
+            result_expo: i32,
+            expected: Price,
+        ) {
+            assert_eq!(
+                price
+                    .get_quantity_value(amount, amount_expo, result_expo)
+                    .unwrap(),
+                expected
+            );
+        }
+
+        fn fails(price: Price, amount: u64, amount_expo: i32, result_expo: i32) {
+            assert_eq!(
+                price.get_quantity_value(amount, amount_expo, result_expo),
+                None
+            );
+        }
+
+        // 1.5 tokens with 6 decimals of a $100 +- $0.10 asset
+        succeeds(pc(10000, 10, -2), 1_500_000, -6, -2, pc(15000, 15, -2));
+        succeeds(pc(10000, 10, -2), 1_500_000, -6, -4, pc(1500000, 1500, -4));
+        succeeds(pc(10000, 10, -2), 1_500_000, -6, 0, pc(150, 0, 0));
+        succeeds(pc(-10000, 10, -2), 1_500_000, -6, -2, pc(-15000, 15, -2));
+
+        // Whole tokens and zero amounts
+        succeeds(pc(10000, 10, -2), 3, 0, -2, pc(30000, 30, -2));
+        succeeds(pc(10000, 10, -2), 0, -6, -2, pc(0, 0, -2));
+
+        // Amount too large for an i64
+        fails(pc(10000, 10, -2), u64::MAX, -6, -2);
+
+        // Result cannot be represented with the requested exponent
+        fails(pc(10000, 10, -2), 1_500_000, -6, -18);
+    }
 }
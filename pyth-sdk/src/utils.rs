@@ -3,6 +3,9 @@
 /// The reason this is added is that `#[serde(with = "String")]` does not work
 /// because Borsh also implements serialize and deserialize functions and
 /// compiler cannot distinguish them.
+///
+/// For backward compatibility with producers that emit JSON numbers, human-readable formats also
+/// accept the number form when deserializing.
 pub mod as_string {
     use serde::de::Error;
     use serde::{
@@ -11,6 +14,13 @@ pub mod as_string {
         Serializer,
     };
 
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrNumber<T> {
+        String(String),
+        Number(T),
+    }
+
     pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
     where
         T: std::fmt::Display,
@@ -21,10 +31,20 @@ pub mod as_string {
 
     pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
     where
-        T: std::str::FromStr,
+        T: std::str::FromStr + Deserialize<'de>,
         D: Deserializer<'de>,
     {
-        let string = String::deserialize(deserializer)?;
+        // Accepting either form requires a self-describing format, so binary formats keep
+        // expecting a string.
+        let string = if deserializer.is_human_readable() {
+            match StringOrNumber::<T>::deserialize(deserializer)? {
+                StringOrNumber::String(string) => string,
+                StringOrNumber::Number(value) => return Ok(value),
+            }
+        } else {
+            String::deserialize(deserializer)?
+        };
+
         string
             .parse()
             .map_err(|_| D::Error::custom("Input is not valid"))